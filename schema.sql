@@ -1,12 +1,37 @@
+DROP TABLE IF EXISTS tags;
+DROP TABLE IF EXISTS categories;
+DROP TABLE IF EXISTS aliases;
 DROP TABLE IF EXISTS posts;
 DROP TABLE IF EXISTS post_content;
 
 CREATE TABLE IF NOT EXISTS posts (
-    id      INTEGER PRIMARY KEY,
-    title   VARCHAR NOT NULL,
-    date    DATETIME NOT NULL,
-    html    VARCHAR NOT NULL,
-    summary VARCHAR(250) NOT NULL,
-    url     VARCHAR(100) NOT NULL,
-    slug    VARCHAR NOT NULL
+    id         INTEGER PRIMARY KEY,
+    title      VARCHAR NOT NULL,
+    date       DATETIME NOT NULL,
+    html       VARCHAR NOT NULL,
+    summary    VARCHAR(250) NOT NULL,
+    url        VARCHAR(100) NOT NULL,
+    slug       VARCHAR NOT NULL,
+    visibility VARCHAR NOT NULL,
+    file_name  VARCHAR NOT NULL UNIQUE,
+    word_count   INTEGER NOT NULL,
+    reading_time INTEGER NOT NULL,
+    toc          VARCHAR
+);
+
+CREATE TABLE IF NOT EXISTS tags (
+    post_id INTEGER NOT NULL REFERENCES posts(id),
+    name    VARCHAR NOT NULL,
+    PRIMARY KEY (post_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS categories (
+    post_id INTEGER NOT NULL REFERENCES posts(id),
+    name    VARCHAR NOT NULL,
+    PRIMARY KEY (post_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS aliases (
+    path    VARCHAR PRIMARY KEY,
+    post_id INTEGER NOT NULL REFERENCES posts(id)
 );
@@ -1,28 +1,65 @@
 //! Static blog generation.
 
+pub mod search;
+
+use std::cmp;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fs::{self, File};
 use std::io::prelude::*;
-use std::path::Path;
+use std::iter;
+use std::path::{Path, PathBuf};
 
-use ammonia::{self, Ammonia};
-use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
 use diesel;
 use diesel::dsl::sql;
-use diesel::expression::{dsl, AsExpression};
+use diesel::expression::AsExpression;
 use diesel::prelude::*;
-use diesel::sql_types::Bool;
-use diesel::sqlite::SqliteConnection;
+use diesel::sql_types::{BigInt, Binary, Bool, Integer, Nullable, Text};
+use diesel::sqlite::{Sqlite, SqliteConnection};
+use diff;
 use log::*;
 use serde::{Deserialize, Serialize};
-use serde_yaml;
+use serde_json;
+use url::percent_encoding::{utf8_percent_encode, PATH_SEGMENT_ENCODE_SET};
+use url::Url;
 
+use crate::config::Config;
 use crate::errors::{self, ErrorKind, ResultExt};
-use crate::markdown::{self, Html, Markdown};
-use crate::models::{NewPost, PostContent, PostLink, Summary};
+use crate::feed::FEED_LENGTH;
+use crate::front_matter;
+use crate::links;
+use crate::markdown::{self, Backend, Extension, Html, Markdown, TocEntry};
+use crate::models::{
+    FeedEntry, NewAlias, NewCategory, NewPost, NewTag, PostContent, PostLink, Summary, TermLink,
+};
+use crate::redirects;
+use crate::sanitize;
+use crate::schema::posts;
+use crate::shortcodes::Shortcodes;
+use crate::slug;
+use crate::wiki_links;
 
-/// The length of a blog post preview.
+/// The maximum number of characters of text in a blog post preview that is not split explicitly.
 const SUMMARY_LENGTH: usize = 200;
 
+/// The marker that ends the preview of a blog post, when written on a line of its own.
+const MORE_MARKER: &str = "<!--more-->";
+
+/// The number of words in a search result snippet.
+const SNIPPET_TOKENS: usize = 30;
+
+/// Delimiters of the matching words in search result snippets.
+const SNIPPET_MATCH_START: char = '\u{2}';
+const SNIPPET_MATCH_END: char = '\u{3}';
+
+/// How much matches in the title, content, tags and categories of a post count towards the
+/// relevance of a search result, in that order.
+const SEARCH_COLUMN_WEIGHTS: &[f64] = &[4.0, 1.0, 2.0, 2.0];
+
+/// The reading speed used to estimate how long a post takes to read.
+const WORDS_PER_MINUTE: usize = 200;
+
 #[allow(missing_docs)]
 mod infix {
     // Diesel doesn't support FTS4 `MATCH` out of the box for SQLite, so we implement it ourselves.
@@ -44,12 +81,29 @@ pub struct Post {
     pub title: String,
 
     /// The time that the post was written.
-    #[serde(with = "human_readable_format")]
-    pub date: NaiveDateTime,
+    pub date: DateTime<Utc>,
+
+    /// The canonical URL of the post.
+    pub url: String,
+
+    /// The number of words in the post.
+    pub word_count: i32,
+
+    /// The estimated number of minutes that it takes to read the post.
+    pub reading_time: i32,
+
+    /// The table of contents of the post, if the post should display one.
+    pub toc: Option<Vec<TocEntry>>,
 
     /// The post rendered as HTML.
     pub html: Html,
 
+    /// The tags attached to the post.
+    pub tags: Vec<TermLink>,
+
+    /// The categories that the post is filed under.
+    pub categories: Vec<TermLink>,
+
     /// The next post chronologically.
     pub next_post: Option<PostLink>,
 
@@ -57,293 +111,2339 @@ pub struct Post {
     pub prev_post: Option<PostLink>,
 }
 
+/// A single page of blog post summaries.
+#[derive(Debug, Serialize)]
+pub struct SummaryPage {
+    /// The summaries on this page.
+    pub posts: Vec<Summary>,
+
+    /// The number of this page, starting from 1.
+    pub page: i64,
+
+    /// The number of the page containing newer posts, if any.
+    pub prev_page: Option<i64>,
+
+    /// The number of the page containing older posts, if any.
+    pub next_page: Option<i64>,
+
+    /// The number of pages available.
+    pub total_pages: i64,
+
+    /// The number of posts on all pages.
+    pub total_posts: i64,
+}
+
+impl SummaryPage {
+    fn new(posts: Vec<Summary>, page: i64, total_pages: i64, total_posts: i64) -> Self {
+        SummaryPage {
+            posts,
+            page,
+            prev_page: if page > 1 { Some(page - 1) } else { None },
+            next_page: if page < total_pages {
+                Some(page + 1)
+            } else {
+                None
+            },
+            total_pages,
+            total_posts,
+        }
+    }
+}
+
+/// Identifies a post by the components of a permalink.
+///
+/// Components that do not appear in the permalink pattern are left unset, and match any post.
+#[derive(Debug, Default)]
+pub struct PostQuery {
+    /// The year that the post was written.
+    pub year: Option<i32>,
+
+    /// The month that the post was written.
+    pub month: Option<u32>,
+
+    /// The day of the month that the post was written.
+    pub day: Option<u32>,
+
+    /// The slug of the post.
+    pub slug: String,
+}
+
+impl PostQuery {
+    /// Creates a query for the post with the given slug that was written on the given day.
+    pub fn on_date(date: NaiveDate, slug: &str) -> Self {
+        PostQuery {
+            year: Some(date.year()),
+            month: Some(date.month()),
+            day: Some(date.day()),
+            slug: slug.to_owned(),
+        }
+    }
+
+    fn matches(&self, date: &DateTime<Tz>) -> bool {
+        self.year.map_or(true, |year| year == date.year())
+            && self.month.map_or(true, |month| month == date.month())
+            && self.day.map_or(true, |day| day == date.day())
+    }
+}
+
+/// Controls where a post may be seen.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    /// The post is a work in progress. It may only be viewed with the preview token.
+    Draft,
+
+    /// The post may be viewed by anyone who knows its URL, but is not listed anywhere.
+    Unlisted,
+
+    /// The post is published and listed everywhere.
+    Public,
+}
+
+impl Visibility {
+    /// Returns the representation of the visibility in the database.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Visibility::Draft => "draft",
+            Visibility::Unlisted => "unlisted",
+            Visibility::Public => "public",
+        }
+    }
+}
+
+impl Default for Visibility {
+    fn default() -> Self {
+        Visibility::Public
+    }
+}
+
+/// A way of grouping blog posts, as specified in each post's metadata.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Taxonomy {
+    /// Freeform labels describing the topics of a post.
+    Tag,
+
+    /// Broad sections of the blog that a post is filed under.
+    Category,
+}
+
+impl Taxonomy {
+    /// Returns the path segment under `/blog` that archives for this taxonomy are served from.
+    pub fn path(self) -> &'static str {
+        match self {
+            Taxonomy::Tag => "tags",
+            Taxonomy::Category => "categories",
+        }
+    }
+
+    /// Returns a link to the archive of posts filed under the given term.
+    pub fn link(self, name: &str) -> TermLink {
+        TermLink {
+            name: name.to_owned(),
+            url: format!(
+                "/blog/{}/{}",
+                self.path(),
+                utf8_percent_encode(name, PATH_SEGMENT_ENCODE_SET)
+            ),
+        }
+    }
+}
+
+/// A tag or category, along with the number of posts filed under it.
+#[derive(Debug, Serialize)]
+pub struct TermCount {
+    /// The name of the tag or category.
+    pub name: String,
+
+    /// The URL of the archive of posts filed under the term.
+    pub url: String,
+
+    /// The number of posts filed under the term.
+    pub count: i64,
+}
+
 /// Retrieves blog post content and metadata by parsing all markdown files in a given directory,
 /// then persists the posts into the database.
-pub fn load<P>(directory: P, conn: &SqliteConnection) -> errors::Result<()>
+pub fn load<P>(
+    directory: P,
+    conn: &SqliteConnection,
+    config: &Config,
+    shortcodes: &Shortcodes,
+) -> errors::Result<()>
 where
     P: AsRef<Path>,
 {
-    use crate::schema::posts::dsl::*;
-
-    let parsed_posts = parse_posts(&directory)?;
+    let parsed_posts = parse_posts(&directory, config, shortcodes)?;
     info!(
         "parsed {} blog posts in {:?}",
         parsed_posts.len(),
         directory.as_ref()
     );
 
-    let new_posts = parsed_posts
-        .iter()
-        .map(|post| {
-            let post_html = markdown::render_html(&post.content);
-            let post_summary = create_summary(&post_html, &post.url());
-
-            NewPost {
-                title: &post.metadata.title,
-                date: post.metadata.date,
-                html: post_html.to_string(),
-                summary: post_summary.to_string(),
-                url: post.url().to_string(),
-                slug: post.slug(),
-            }
-        })
-        .collect::<Vec<_>>();
+    conn.transaction::<_, errors::Error, _>(|| {
+        for post in &parsed_posts {
+            insert_post(conn, post, config)?;
+        }
 
-    diesel::insert_into(posts)
-        .values(&new_posts)
-        .execute(conn)?;
+        Ok(())
+    })?;
 
-    create_fts_index(conn)?;
+    create_fts_index(conn, &config.search)?;
 
     Ok(())
 }
 
-/// Creates the full text search index for the blog posts.
-pub fn create_fts_index(conn: &SqliteConnection) -> errors::Result<()> {
-    use crate::schema::post_content;
-    use crate::schema::posts::dsl::*;
+/// The differences between the HTML that two markdown backends render for a post.
+#[derive(Debug)]
+pub struct RenderingDifference {
+    /// The name of the file in the blog directory that the post was parsed from.
+    pub file_name: String,
 
-    sql::<Bool>(r#"CREATE VIRTUAL TABLE post_content USING fts4(content, title)"#).execute(conn)?;
+    /// The lines of HTML that differ, prefixed with `-` if they were only rendered by the first
+    /// backend or `+` if they were only rendered by the second.
+    pub diff: String,
+}
 
-    // TODO: We should actually load the content here, not the HTML.
-    let new_post_content = posts.select((id, title, html)).load::<PostContent>(conn)?;
+/// Renders every post in a directory with two markdown backends, returning the posts whose HTML
+/// differs.
+///
+/// Each post is rendered with the extensions that it would be rendered with when loaded.
+pub fn compare_renderers<P>(
+    directory: P,
+    config: &Config,
+    shortcodes: &Shortcodes,
+    first: Backend,
+    second: Backend,
+) -> errors::Result<Vec<RenderingDifference>>
+where
+    P: AsRef<Path>,
+{
+    let parsed_posts = parse_posts(&directory, config, shortcodes)?;
 
-    diesel::insert_into(post_content::table)
-        .values(&new_post_content)
-        .execute(conn)?;
+    let mut differences = vec![];
+    for post in parsed_posts {
+        let raw_html = config.sanitize.raw_html.posts;
+        let first_html = markdown::render_html(&post.content, first, &post.extensions, raw_html);
+        let second_html = markdown::render_html(&post.content, second, &post.extensions, raw_html);
 
-    info!("optimizing blog post content index");
-    sql::<Bool>(r#"INSERT INTO post_content(post_content) VALUES ('optimize')"#).execute(conn)?;
+        let diff = diff::lines(&first_html, &second_html)
+            .into_iter()
+            .filter_map(|line| match line {
+                diff::Result::Left(line) => Some(format!("-{}\n", line)),
+                diff::Result::Right(line) => Some(format!("+{}\n", line)),
+                diff::Result::Both(..) => None,
+            })
+            .collect::<String>();
 
-    Ok(())
+        if !diff.is_empty() {
+            differences.push(RenderingDifference {
+                file_name: post.file_name,
+                diff,
+            });
+        }
+    }
+
+    differences.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+    Ok(differences)
 }
 
-/// Searches post contents and titles with a text query.
+/// Brings the database up to date with a single post file that has been created, modified or
+/// deleted since the blog was loaded.
 ///
-/// Returns summaries of the posts that contain the query.
-pub fn find_summaries(conn: &SqliteConnection, query: &str) -> errors::Result<Vec<Summary>> {
-    use crate::schema::post_content;
-    use crate::schema::post_content::dsl as content_dsl;
-    use crate::schema::posts::dsl::*;
+/// If the title, slug or URL of the post changed, the other posts in its directory that link to it
+/// are rendered again. The reload fails if any of their links no longer resolve.
+///
+/// The full text search index must already exist.
+pub fn reload_post<P>(
+    path: P,
+    conn: &SqliteConnection,
+    config: &Config,
+    shortcodes: &Shortcodes,
+) -> errors::Result<()>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let file_name = file_name_of(path)?;
 
-    let matching_ids = post_content::table
-        .select(content_dsl::docid)
-        .filter(fts_match(content_dsl::content, query));
-    let summaries = posts
-        .select((title, date, summary, url))
-        .filter(id.eq_any(matching_ids))
-        .load::<Summary>(conn)?;
+    conn.transaction::<_, errors::Error, _>(|| {
+        let old_target = posts::table
+            .select((posts::title, posts::slug, posts::url))
+            .filter(posts::file_name.eq(&file_name))
+            .first::<(String, String, String)>(conn)
+            .optional()?;
+        remove_post(conn, &file_name)?;
 
-    Ok(summaries)
+        let new_target = if path.exists() {
+            let post = parse_post_file(path.to_owned(), config, shortcodes)?;
+            let target = (post.metadata.title.clone(), post.slug(), post.url.clone());
+            store_post(conn, post, config)?;
+            info!("reloaded blog post {:?}", path);
+            Some(target)
+        } else {
+            info!("removed blog post {:?}", path);
+            None
+        };
+
+        if old_target != new_target {
+            let names = old_target
+                .into_iter()
+                .chain(new_target)
+                .flat_map(|(title, slug, _)| vec![title, slug])
+                .collect::<HashSet<_>>();
+            let directory = match path.parent() {
+                Some(directory) if !directory.as_os_str().is_empty() => directory,
+                _ => Path::new("."),
+            };
+            relink_posts(directory, &file_name, &names, conn, config, shortcodes)?;
+        }
+
+        Ok(())
+    })
 }
 
-/// Retrieves a blog post from the database given the date it was posted and its title.
-pub fn get_post(
+/// Renders the posts in a directory that link to any of the given titles or slugs again, other
+/// than the post parsed from `skipped_file_name`.
+fn relink_posts(
+    directory: &Path,
+    skipped_file_name: &str,
+    names: &HashSet<String>,
     conn: &SqliteConnection,
-    post_date: &NaiveDate,
-    post_slug: &str,
-) -> errors::Result<Post> {
-    use crate::schema::posts::dsl::*;
+    config: &Config,
+    shortcodes: &Shortcodes,
+) -> errors::Result<()> {
+    let entries = fs::read_dir(directory).chain_err(|| "could not read blog posts directory")?;
 
-    // TODO: We should be able to do this in a single query.
+    for entry in entries {
+        let path = entry?.path();
+        if !is_post_file(&path) || file_name_of(&path)? == skipped_file_name {
+            continue;
+        }
 
-    let post = posts
-        .select((id, title, html, date, url))
-        .filter(slug.eq(post_slug))
-        .filter(dsl::date(date).eq(post_date))
-        .first::<crate::models::Post>(conn)?;
+        let post = parse_post_file(path.clone(), config, shortcodes)?;
+        if wiki_links::targets(&post.content)
+            .iter()
+            .any(|target| names.contains(target))
+        {
+            remove_post(conn, &post.file_name)?;
+            store_post(conn, post, config)?;
+            info!("updated links in blog post {:?}", path);
+        }
+    }
 
-    let next_post = posts
-        .select((title, url))
-        .order(date.asc())
-        .filter(dsl::date(date).gt(post_date))
-        .first::<PostLink>(conn)
-        .optional()?;
+    Ok(())
+}
 
-    let prev_post = posts
-        .select((title, url))
-        .order(date.desc())
-        .filter(dsl::date(date).lt(post_date))
-        .first::<PostLink>(conn)
-        .optional()?;
+/// Resolves the wiki links of a post against the posts in the database and the post itself, then
+/// persists and indexes it.
+fn store_post(
+    conn: &SqliteConnection,
+    mut post: ParsedPost,
+    config: &Config,
+) -> errors::Result<()> {
+    let other_posts = posts::table
+        .select((posts::title, posts::slug, posts::url))
+        .load::<(String, String, String)>(conn)?;
+    let targets = link_targets(other_posts.into_iter().chain(iter::once((
+        post.metadata.title.clone(),
+        post.slug(),
+        post.url.clone(),
+    ))));
+    resolve_links(&mut post, &targets)?;
 
-    Ok(Post {
-        title: String::from(post.title.as_str()),
-        date: post.date.clone(),
-        html: Html::new(post.html.to_string()),
-        next_post: next_post,
-        prev_post: prev_post,
-    })
+    let post_id = insert_post(conn, &post, config)?;
+    index_post(conn, post_id)?;
+    Ok(())
 }
 
-/// Retrieves blog post summaries from the database.
-pub fn get_summaries(conn: &SqliteConnection) -> errors::Result<Vec<Summary>> {
-    use crate::schema::posts::dsl::*;
-
-    Ok(posts
-        .select((title, date, summary, url))
-        .order(date.desc())
-        .load::<Summary>(conn)?)
+/// Returns whether the file at a path should be parsed as a blog post.
+pub fn is_post_file(path: &Path) -> bool {
+    path.extension()
+        .map_or(false, |extension| extension == "md")
 }
 
-#[derive(Debug)]
-struct ParsedPost {
-    metadata: Metadata,
-    content: Markdown,
+fn file_name_of(path: &Path) -> errors::Result<String> {
+    path.file_name()
+        .and_then(|file_name| file_name.to_str())
+        .map(String::from)
+        .ok_or_else(|| ErrorKind::PostParse(path.to_owned()).into())
 }
 
-impl ParsedPost {
-    /// Returns a relative URL to the given post.
-    fn url(&self) -> String {
-        let date = &self.metadata.date;
+/// Persists a parsed post, along with its tags and categories, returning the ID of the new post.
+fn insert_post(conn: &SqliteConnection, post: &ParsedPost, config: &Config) -> errors::Result<i32> {
+    use crate::schema::posts::dsl::*;
 
-        // TODO: I'd like to return a String here, but url::Url doesn't allow non-relative URLs. We
-        // could work around this if we knew the server name.
-        format!(
-            "/blog/{}/{}/{}/{}",
-            date.year(),
-            date.month(),
-            date.day(),
-            self.slug()
-        )
-    }
+    let post_url = post.url.clone();
 
-    /// Returns the escaped title of the post, for use in the URL.
-    fn slug(&self) -> String {
-        self.metadata.title.to_lowercase().replace(' ', "-")
+    let existing_file_name = posts
+        .select(file_name)
+        .filter(url.eq(&post_url))
+        .first::<String>(conn)
+        .optional()?;
+    if let Some(existing_file_name) = existing_file_name {
+        bail!(ErrorKind::SlugCollision(
+            post_url,
+            existing_file_name,
+            post.file_name.clone()
+        ));
     }
-}
 
-#[derive(Debug, Deserialize)]
-struct Metadata {
-    title: String,
-    #[serde(with = "on_disk_format")]
-    date: NaiveDateTime,
-    categories: Vec<String>,
-    tags: Vec<String>,
-}
+    let (post_content, summary_content) = split_summary(&post.content);
 
-fn parse_post<R>(reader: &mut R) -> errors::Result<ParsedPost>
-where
-    R: Read,
-{
-    let post = {
-        let mut post = String::new();
-        reader
-            .read_to_string(&mut post)
-            .chain_err(|| "could not read contents of post")?;
-        post
+    let document = markdown::render(
+        &post_content,
+        post.backend,
+        &post.extensions,
+        config.sanitize.raw_html.posts,
+    );
+    let post_html = config.sanitize.clean(&document.html);
+    let post_toc = if post.metadata.toc {
+        let entries = markdown::table_of_contents(&document.headings);
+        Some(serde_json::to_string(&entries)?)
+    } else {
+        None
     };
+    let summary_html = match post.metadata.summary.as_ref().or(summary_content.as_ref()) {
+        Some(summary_markdown) => {
+            let rendered = markdown::render_html(
+                summary_markdown,
+                post.backend,
+                &post.extensions,
+                config.sanitize.raw_html.posts,
+            );
+            format!("{}<p>", rendered.trim_end())
+        }
+        None => format!("<p>{} ", truncate_text(&post_html, SUMMARY_LENGTH)),
+    };
+    let post_summary = create_summary(&summary_html, &post_url, &config.sanitize);
+    // Code is not read like prose, and highlighting splits it into many small elements.
+    let post_word_count = post_html.prose().word_count();
 
-    // Read up to the first double newline to determine the end of metadata.
-    //
-    // TODO: This could probably be more robust. Investigate separating the metadata and
-    // markdown as separate YAML documents.
-    let contents = post.splitn(2, "\n\n").collect::<Vec<_>>();
+    let post_html = links::decorate(&post_html, &config.links, &config.site_url);
+    let post_summary = links::decorate(&post_summary, &config.links, &config.site_url);
 
-    let metadata = serde_yaml::from_str(contents[0])?;
+    let new_post = NewPost {
+        title: &post.metadata.title,
+        date: post.date.naive_utc(),
+        html: post_html.to_string(),
+        summary: post_summary.to_string(),
+        url: post_url.clone(),
+        slug: post.slug(),
+        visibility: post.metadata.visibility.as_str(),
+        file_name: &post.file_name,
+        word_count: post_word_count as i32,
+        reading_time: reading_time_of(post_word_count) as i32,
+        toc: post_toc,
+    };
 
-    Ok(ParsedPost {
-        metadata: metadata,
-        content: Markdown::new(contents[1].to_owned()),
-    })
+    diesel::insert_into(posts).values(&new_post).execute(conn)?;
+    let post_id = diesel::select(sql::<Integer>("last_insert_rowid()")).first(conn)?;
+
+    insert_terms(conn, post_id, &post.metadata)?;
+    insert_aliases(conn, post_id, &post.metadata)?;
+
+    Ok(post_id)
 }
 
-fn create_summary(html: &Html, url: &str) -> Html {
-    let ammonia = Ammonia {
-        url_relative: ammonia::UrlRelative::PassThrough,
-        ..Default::default()
-    };
+/// Deletes the post that was parsed from the given file, if any, along with everything that
+/// refers to it.
+fn remove_post(conn: &SqliteConnection, post_file_name: &str) -> errors::Result<()> {
+    use crate::schema::posts::dsl::*;
+    use crate::schema::{aliases, categories, post_content, tags};
 
-    let summary_link = format!(r#"… <a href="{}">Continue&rarr;</a>"#, url);
-    let summary = html
-        .chars()
-        .take(SUMMARY_LENGTH)
-        .chain(summary_link.chars())
-        .collect::<String>();
+    let post_ids = posts
+        .select(id)
+        .filter(file_name.eq(post_file_name))
+        .load::<i32>(conn)?;
 
-    // Sanitize the summary so that any unclosed tags are closed again.
-    let summary = ammonia.clean(&summary);
+    diesel::delete(tags::table.filter(tags::post_id.eq_any(&post_ids))).execute(conn)?;
+    diesel::delete(categories::table.filter(categories::post_id.eq_any(&post_ids)))
+        .execute(conn)?;
+    diesel::delete(aliases::table.filter(aliases::post_id.eq_any(&post_ids))).execute(conn)?;
+    diesel::delete(post_content::table.filter(post_content::docid.eq_any(&post_ids)))
+        .execute(conn)?;
+    diesel::delete(posts.filter(id.eq_any(&post_ids))).execute(conn)?;
 
-    Html::new(summary)
+    Ok(())
 }
 
-fn parse_posts<P>(directory: P) -> errors::Result<Vec<ParsedPost>>
-where
-    P: AsRef<Path>,
-{
-    let entries = fs::read_dir(directory)
-        .chain_err(|| "could not read blog posts directory")?
-        .into_iter();
+/// Persists the paths that should redirect to a post.
+fn insert_aliases(
+    conn: &SqliteConnection,
+    post_id: i32,
+    metadata: &Metadata,
+) -> errors::Result<()> {
+    use crate::schema::aliases;
 
-    entries
-        .map(|entry| {
-            let entry = entry.unwrap();
-            let mut file =
-                File::open(entry.path()).chain_err(|| "error opening directory entry")?;
-            let post = parse_post(&mut file)
-                .chain_err(|| ErrorKind::PostParse(entry.path().to_owned()))?;
-            Ok(post)
-        })
-        .collect()
+    let paths = metadata
+        .aliases
+        .iter()
+        .map(|alias| redirects::normalize(alias))
+        .collect::<BTreeSet<_>>();
+
+    for path in &paths {
+        diesel::insert_into(aliases::table)
+            .values(&NewAlias { path, post_id })
+            .execute(conn)
+            .chain_err(|| format!("the alias {} is used by more than one post", path))?;
+    }
+
+    Ok(())
 }
 
-mod on_disk_format {
-    use chrono::NaiveDateTime;
-    use serde::{self, Deserialize, Deserializer};
+/// Persists the tags and categories of a post.
+fn insert_terms(conn: &SqliteConnection, post_id: i32, metadata: &Metadata) -> errors::Result<()> {
+    use crate::schema::{categories, tags};
 
-    /// The date format used in the blog posts' markdown files.
-    const ON_DISK_FORMAT: &str = "%l:%M%P %m/%d/%y";
+    // Duplicate terms would violate the primary key, and are meaningless anyways.
+    let post_tags = metadata
+        .tags
+        .iter()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|name| NewTag { post_id, name })
+        .collect::<Vec<_>>();
 
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<NaiveDateTime, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let s = String::deserialize(deserializer)?;
-        NaiveDateTime::parse_from_str(&s, ON_DISK_FORMAT).map_err(serde::de::Error::custom)
-    }
+    let post_categories = metadata
+        .categories
+        .iter()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|name| NewCategory { post_id, name })
+        .collect::<Vec<_>>();
+
+    diesel::insert_into(tags::table)
+        .values(&post_tags)
+        .execute(conn)?;
+    diesel::insert_into(categories::table)
+        .values(&post_categories)
+        .execute(conn)?;
+
+    Ok(())
 }
 
-/// Module intended to be used by `serde(with)` to format human-readable dates for display.
-pub mod human_readable_format {
-    use chrono::NaiveDateTime;
-    use serde::Serializer;
+/// Creates the full text search index for the blog posts, using the configured tokenizer.
+///
+/// Any existing index is replaced, so the index is rebuilt whenever the tokenizer changes, even if
+/// the database outlives the server.
+pub fn create_fts_index(conn: &SqliteConnection, options: &search::Options) -> errors::Result<()> {
+    use crate::schema::post_content;
+    use crate::schema::posts::dsl::*;
 
-    /// The date format suitable for displaying on the website.
-    pub const HUMAN_READABLE_FORMAT: &str = "%B %e, %Y";
+    let definition = format!(
+        "CREATE VIRTUAL TABLE post_content USING fts4(title, content, tags, categories, {})",
+        options.tokenize_argument()
+    );
 
-    /// Serializes a date into a human-readable format.
-    pub fn serialize<S>(date: &NaiveDateTime, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        serializer.serialize_str(&date.format(HUMAN_READABLE_FORMAT).to_string())
+    let existing_definition = diesel::select(sql::<Nullable<Text>>(
+        "(SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'post_content')",
+    ))
+    .get_result::<Option<String>>(conn)?;
+    if let Some(existing_definition) = existing_definition {
+        if existing_definition != definition {
+            info!("search tokenizer changed, rebuilding blog post content index");
+        }
+        sql::<Bool>("DROP TABLE post_content").execute(conn)?;
     }
+
+    sql::<Bool>(&definition).execute(conn)?;
+
+    let post_ids = posts.select(id).load::<i32>(conn)?;
+    let new_post_content = search_index_rows(conn, &post_ids)?;
+
+    diesel::insert_into(post_content::table)
+        .values(&new_post_content)
+        .execute(conn)?;
+
+    info!("optimizing blog post content index");
+    sql::<Bool>(r#"INSERT INTO post_content(post_content) VALUES ('optimize')"#).execute(conn)?;
+
+    Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::markdown::Html;
+/// Adds a single post to the full text search index.
+fn index_post(conn: &SqliteConnection, post_id: i32) -> errors::Result<()> {
+    use crate::schema::post_content;
 
-    #[test]
-    fn parse_all_posts() {
-        super::parse_posts("blog").unwrap();
-    }
+    let new_post_content = search_index_rows(conn, &[post_id])?;
 
-    #[test]
-    fn summary_sanitization() {
-        let text = "<p>Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do \
-                    eiusmod tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim \
-                    veniam, quis nostrud exercitation ullamco laboris nisi ut aliquip ex ea \
-                    commodo consequat. Duis aute irure dolor in reprehenderit in voluptate velit \
-                    esse cillum dolore eu fugiat nulla pariatur. Excepteur sint occaecat \
-                    cupidatat non proident, sunt in culpa qui officia deserunt mollit anim id est \
-                    laborum.</p>";
-        let html = Html::new(String::from(text));
-        assert!(html.len() > super::SUMMARY_LENGTH);
+    diesel::insert_into(post_content::table)
+        .values(&new_post_content)
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Returns the rows of the full text search index for some posts.
+///
+/// Posts are indexed by the text of their rendered markdown, so that markup and attribute values
+/// do not match searches. Tags and categories are indexed in columns of their own.
+fn search_index_rows(
+    conn: &SqliteConnection,
+    post_ids: &[i32],
+) -> errors::Result<Vec<PostContent>> {
+    use crate::schema::posts::dsl::*;
 
-        let summary = super::create_summary(&html, "http://google.com");
+    let post_html = posts
+        .select((id, title, html))
+        .filter(id.eq_any(post_ids))
+        .load::<(i32, String, String)>(conn)?;
 
-        assert!(summary.ends_with("</p>"));
+    let mut post_tags = load_terms(conn, Taxonomy::Tag, post_ids)?;
+    let mut post_categories = load_terms(conn, Taxonomy::Category, post_ids)?;
+    let names = |terms: &mut HashMap<i32, Vec<TermLink>>, post_id| {
+        terms
+            .remove(&post_id)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|term| term.name)
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    let rows = post_html
+        .into_iter()
+        .map(|(post_id, post_title, post_html)| PostContent {
+            docid: post_id,
+            title: post_title,
+            content: Html::new(post_html)
+                .plain_text()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" "),
+            tags: names(&mut post_tags, post_id),
+            categories: names(&mut post_categories, post_id),
+        })
+        .collect();
+
+    Ok(rows)
+}
+
+/// Searches post titles, contents, tags and categories with a text query.
+///
+/// Returns a page of summaries of the posts that contain the query, most relevant first. The
+/// summary of each post is replaced by an excerpt of the post with the matching words highlighted.
+///
+/// The query is written in the [search query language](search/index.html). Dates in the query are
+/// interpreted in the configured time zone, and configured stop words are ignored. Queries without
+/// any text to search for match every post that passes their filters, newest first, and keep the
+/// usual summaries.
+pub fn find_summaries(
+    conn: &SqliteConnection,
+    query: &str,
+    config: &Config,
+    page: i64,
+    per_page: i64,
+) -> errors::Result<SummaryPage> {
+    use crate::schema::posts::dsl::*;
+
+    let mut query = search::Query::parse(query);
+    query.remove_stop_words(&config.search.stop_words);
+
+    let candidates = search_filters(&query, config.timezone)
+        .select(id)
+        .order(date.desc())
+        .load::<i32>(conn)?;
+
+    let matches = match query.fts_query() {
+        Some(fts_query) => {
+            let candidates = candidates.into_iter().collect::<HashSet<_>>();
+            let mut matches = search_index(conn, &fts_query)?
+                .into_iter()
+                .filter(|m| candidates.contains(&m.post_id))
+                .collect::<Vec<_>>();
+            matches.sort_by(|a, b| b.rank.partial_cmp(&a.rank).unwrap_or(cmp::Ordering::Equal));
+            matches
+        }
+        None => candidates
+            .into_iter()
+            .map(|post_id| SearchMatch {
+                post_id,
+                rank: 0.0,
+                snippet: None,
+            })
+            .collect(),
+    };
+
+    let total = matches.len() as i64;
+    let total_pages = check_page(page, per_page, total)?;
+
+    let page_matches = matches
+        .into_iter()
+        .skip(((page - 1) * per_page) as usize)
+        .take(per_page as usize)
+        .collect::<Vec<_>>();
+
+    let mut summaries = posts
+        .select((id, title, date, summary, url, word_count, reading_time))
+        .filter(id.eq_any(page_matches.iter().map(|m| m.post_id).collect::<Vec<_>>()))
+        .load::<Summary>(conn)?
+        .into_iter()
+        .map(|post_summary| (post_summary.id, post_summary))
+        .collect::<HashMap<_, _>>();
+
+    let summaries = page_matches
+        .into_iter()
+        .filter_map(|m| {
+            let mut post_summary = summaries.remove(&m.post_id)?;
+            if let Some(ref snippet) = m.snippet {
+                post_summary.summary = snippet_html(snippet);
+            }
+            Some(post_summary)
+        })
+        .collect();
+
+    Ok(SummaryPage::new(
+        attach_terms(conn, summaries)?,
+        page,
+        total_pages,
+        total,
+    ))
+}
+
+/// Returns the listed posts that pass the filters of a search query, apart from the text that
+/// they must contain.
+fn search_filters<'a>(query: &search::Query, timezone: Tz) -> posts::BoxedQuery<'a, Sqlite> {
+    use crate::schema::post_content::dsl as content_dsl;
+    use crate::schema::posts::dsl::*;
+    use crate::schema::{categories, tags};
+
+    let mut filtered = posts.filter(listed()).into_boxed();
+
+    for clause in &query.clauses {
+        filtered = match *clause {
+            search::Clause::Text {
+                field,
+                ref text,
+                negated: true,
+            } => {
+                let matching_ids =
+                    content_dsl::post_content
+                        .select(content_dsl::docid)
+                        .filter(fts_match(
+                            sql::<Text>("post_content"),
+                            search::fts_text(field, text),
+                        ));
+                filtered.filter(id.ne_all(matching_ids))
+            }
+            search::Clause::Text { .. } => filtered,
+            search::Clause::Term {
+                taxonomy,
+                ref name,
+                negated,
+            } => {
+                let name = name.to_owned();
+                match (taxonomy, negated) {
+                    (Taxonomy::Tag, false) => filtered.filter(
+                        id.eq_any(
+                            tags::table
+                                .select(tags::post_id)
+                                .filter(tags::name.eq(name)),
+                        ),
+                    ),
+                    (Taxonomy::Tag, true) => filtered.filter(
+                        id.ne_all(
+                            tags::table
+                                .select(tags::post_id)
+                                .filter(tags::name.eq(name)),
+                        ),
+                    ),
+                    (Taxonomy::Category, false) => filtered.filter(
+                        id.eq_any(
+                            categories::table
+                                .select(categories::post_id)
+                                .filter(categories::name.eq(name)),
+                        ),
+                    ),
+                    (Taxonomy::Category, true) => filtered.filter(
+                        id.ne_all(
+                            categories::table
+                                .select(categories::post_id)
+                                .filter(categories::name.eq(name)),
+                        ),
+                    ),
+                }
+            }
+            // Days that do not exist in the time zone cannot be compared against, so the filter
+            // matches no posts.
+            search::Clause::After(day) => match start_of_day(day, timezone) {
+                Some(start) => filtered.filter(date.ge(start)),
+                None => filtered.filter(sql::<Bool>("0")),
+            },
+            search::Clause::Before(day) => match start_of_day(day, timezone) {
+                Some(start) => filtered.filter(date.lt(start)),
+                None => filtered.filter(sql::<Bool>("0")),
+            },
+        };
+    }
+
+    filtered
+}
+
+/// A post that matches a search, before it is known whether the post is listed.
+struct SearchMatch {
+    post_id: i32,
+    rank: f64,
+    snippet: Option<String>,
+}
+
+/// Returns every post in the full text search index that matches a query.
+fn search_index(conn: &SqliteConnection, query: &str) -> errors::Result<Vec<SearchMatch>> {
+    use crate::schema::post_content::dsl::*;
+
+    // The indexed text is not escaped, so the snippet is marked up with control characters that
+    // cannot appear in it, and converted to HTML afterwards.
+    let matches = post_content
+        .select((
+            docid,
+            sql::<Binary>("matchinfo(post_content, 'pcnalx')"),
+            sql::<Text>(&format!(
+                "snippet(post_content, '{}', '{}', '…', -1, {})",
+                SNIPPET_MATCH_START, SNIPPET_MATCH_END, SNIPPET_TOKENS
+            )),
+        ))
+        .filter(fts_match(sql::<Text>("post_content"), query))
+        .load::<(i32, Vec<u8>, String)>(conn)?
+        .into_iter()
+        .map(|(post_id, matchinfo, snippet)| SearchMatch {
+            post_id,
+            rank: bm25(&matchinfo),
+            snippet: Some(snippet),
+        })
+        .collect();
+
+    Ok(matches)
+}
+
+/// Scores how relevant a post is to a search with the Okapi BM25 ranking function, using the
+/// output of the `matchinfo` function with the `pcnalx` format string.
+///
+/// Matches in each column of the index are weighted by `SEARCH_COLUMN_WEIGHTS`.
+fn bm25(matchinfo: &[u8]) -> f64 {
+    const K1: f64 = 1.2;
+    const B: f64 = 0.75;
+
+    let info = matchinfo
+        .chunks(4)
+        .map(|chunk| f64::from(u32::from_ne_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])))
+        .collect::<Vec<_>>();
+
+    let phrases = info[0] as usize;
+    let columns = info[1] as usize;
+    let documents = info[2];
+    let average_lengths = &info[3..3 + columns];
+    let lengths = &info[3 + columns..3 + 2 * columns];
+    let hits = &info[3 + 2 * columns..];
+
+    let mut score = 0.0;
+    for phrase in 0..phrases {
+        for column in 0..columns {
+            let offset = 3 * (phrase * columns + column);
+            let frequency = hits[offset];
+            let matching_documents = hits[offset + 2];
+            if frequency == 0.0 {
+                continue;
+            }
+
+            let idf = ((documents - matching_documents + 0.5) / (matching_documents + 0.5))
+                .ln()
+                .max(f64::EPSILON);
+            let length_ratio = if average_lengths[column] > 0.0 {
+                lengths[column] / average_lengths[column]
+            } else {
+                1.0
+            };
+            let weight = SEARCH_COLUMN_WEIGHTS.get(column).cloned().unwrap_or(1.0);
+
+            score += weight * idf * frequency * (K1 + 1.0)
+                / (frequency + K1 * (1.0 - B + B * length_ratio));
+        }
+    }
+
+    score
+}
+
+/// Converts a snippet of the search index into HTML, highlighting the matching words.
+fn snippet_html(snippet: &str) -> String {
+    let text = snippet
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace(SNIPPET_MATCH_START, "<mark>")
+        .replace(SNIPPET_MATCH_END, "</mark>");
+    format!("<p>{}</p>", text)
+}
+
+/// Retrieves summaries of all posts filed under a tag or category, newest first.
+pub fn find_summaries_by_term(
+    conn: &SqliteConnection,
+    taxonomy: Taxonomy,
+    term: &str,
+) -> errors::Result<Vec<Summary>> {
+    use crate::schema::posts::dsl::*;
+
+    let post_ids = find_post_ids_by_term(conn, taxonomy, term)?;
+
+    let summaries = posts
+        .select((id, title, date, summary, url, word_count, reading_time))
+        .filter(id.eq_any(post_ids))
+        .filter(listed())
+        .order(date.desc())
+        .load::<Summary>(conn)?;
+
+    attach_terms(conn, summaries)
+}
+
+/// Retrieves the most recent posts for syndication, optionally restricted to those filed under a
+/// tag or category.
+pub fn get_feed_entries(
+    conn: &SqliteConnection,
+    term: Option<(Taxonomy, &str)>,
+) -> errors::Result<Vec<FeedEntry>> {
+    use crate::schema::posts::dsl::*;
+
+    let mut query = posts
+        .select((title, date, html, summary, url))
+        .filter(listed())
+        .order(date.desc())
+        .limit(FEED_LENGTH)
+        .into_boxed();
+
+    if let Some((taxonomy, term)) = term {
+        query = query.filter(id.eq_any(find_post_ids_by_term(conn, taxonomy, term)?));
+    }
+
+    Ok(query.load::<FeedEntry>(conn)?)
+}
+
+fn find_post_ids_by_term(
+    conn: &SqliteConnection,
+    taxonomy: Taxonomy,
+    term: &str,
+) -> errors::Result<Vec<i32>> {
+    use crate::schema::{categories, tags};
+
+    let post_ids = match taxonomy {
+        Taxonomy::Tag => tags::table
+            .select(tags::post_id)
+            .filter(tags::name.eq(term))
+            .load::<i32>(conn)?,
+        Taxonomy::Category => categories::table
+            .select(categories::post_id)
+            .filter(categories::name.eq(term))
+            .load::<i32>(conn)?,
+    };
+
+    Ok(post_ids)
+}
+
+/// Retrieves every tag or category in use, along with the number of posts filed under each.
+pub fn get_term_counts(
+    conn: &SqliteConnection,
+    taxonomy: Taxonomy,
+) -> errors::Result<Vec<TermCount>> {
+    use crate::schema::{categories, tags};
+
+    let published = posts::visibility
+        .eq(Visibility::Public.as_str())
+        .and(posts::date.le(now()));
+
+    let counts = match taxonomy {
+        Taxonomy::Tag => tags::table
+            .inner_join(posts::table)
+            .filter(published)
+            .select((tags::name, sql::<BigInt>("COUNT(*)")))
+            .group_by(tags::name)
+            .order(tags::name.asc())
+            .load::<(String, i64)>(conn)?,
+        Taxonomy::Category => categories::table
+            .inner_join(posts::table)
+            .filter(published)
+            .select((categories::name, sql::<BigInt>("COUNT(*)")))
+            .group_by(categories::name)
+            .order(categories::name.asc())
+            .load::<(String, i64)>(conn)?,
+    };
+
+    Ok(counts
+        .into_iter()
+        .map(|(name, count)| {
+            let TermLink { name, url } = taxonomy.link(&name);
+            TermCount { name, url, count }
+        })
+        .collect())
+}
+
+/// Loads the terms of a taxonomy for each of the given posts, keyed by post ID.
+fn load_terms(
+    conn: &SqliteConnection,
+    taxonomy: Taxonomy,
+    post_ids: &[i32],
+) -> errors::Result<HashMap<i32, Vec<TermLink>>> {
+    use crate::schema::{categories, tags};
+
+    let rows = match taxonomy {
+        Taxonomy::Tag => tags::table
+            .filter(tags::post_id.eq_any(post_ids))
+            .order(tags::name.asc())
+            .load::<(i32, String)>(conn)?,
+        Taxonomy::Category => categories::table
+            .filter(categories::post_id.eq_any(post_ids))
+            .order(categories::name.asc())
+            .load::<(i32, String)>(conn)?,
+    };
+
+    let mut terms = HashMap::new();
+    for (post_id, name) in rows {
+        terms
+            .entry(post_id)
+            .or_insert_with(Vec::new)
+            .push(taxonomy.link(&name));
+    }
+
+    Ok(terms)
+}
+
+/// Fills in the tags and categories of queried summaries.
+fn attach_terms(
+    conn: &SqliteConnection,
+    mut summaries: Vec<Summary>,
+) -> errors::Result<Vec<Summary>> {
+    let post_ids = summaries
+        .iter()
+        .map(|summary| summary.id)
+        .collect::<Vec<_>>();
+
+    let mut post_tags = load_terms(conn, Taxonomy::Tag, &post_ids)?;
+    let mut post_categories = load_terms(conn, Taxonomy::Category, &post_ids)?;
+
+    for summary in &mut summaries {
+        summary.tags = post_tags.remove(&summary.id).unwrap_or_default();
+        summary.categories = post_categories.remove(&summary.id).unwrap_or_default();
+    }
+
+    Ok(summaries)
+}
+
+/// Retrieves a blog post from the database given the components of its permalink.
+///
+/// Dates in the query are compared against the time that the post was written in the given time
+/// zone. The URL of the returned post may differ from the URL that was requested.
+///
+/// Drafts and posts scheduled for the future are only retrieved if `preview` is set. The next and
+/// previous posts are always chosen from the listed posts.
+pub fn get_post(
+    conn: &SqliteConnection,
+    post_query: &PostQuery,
+    preview: bool,
+    timezone: Tz,
+) -> errors::Result<Post> {
+    use crate::schema::posts::dsl::*;
+
+    // TODO: We should be able to do this in a single query.
+
+    let mut query = posts
+        .select((id, title, html, date, url, word_count, reading_time, toc))
+        .filter(slug.eq(&post_query.slug))
+        .into_boxed();
+
+    if !preview {
+        query = query
+            .filter(visibility.ne(Visibility::Draft.as_str()))
+            .filter(date.le(now()));
+    }
+
+    // Dates in permalinks are in the configured time zone, which SQLite knows nothing about, so
+    // posts with the same slug are compared in Rust instead.
+    let post = query
+        .load::<crate::models::Post>(conn)?
+        .into_iter()
+        .find(|post| post_query.matches(&timezone.from_utc_datetime(&post.date)))
+        .ok_or(diesel::NotFound)?;
+
+    let next_post = posts
+        .select((title, url))
+        .order(date.asc())
+        .filter(date.gt(post.date))
+        .filter(listed())
+        .first::<PostLink>(conn)
+        .optional()?;
+
+    let prev_post = posts
+        .select((title, url))
+        .order(date.desc())
+        .filter(date.lt(post.date))
+        .filter(listed())
+        .first::<PostLink>(conn)
+        .optional()?;
+
+    let tags = load_terms(conn, Taxonomy::Tag, &[post.id])?
+        .remove(&post.id)
+        .unwrap_or_default();
+    let categories = load_terms(conn, Taxonomy::Category, &[post.id])?
+        .remove(&post.id)
+        .unwrap_or_default();
+
+    Ok(Post {
+        title: String::from(post.title.as_str()),
+        date: DateTime::from_utc(post.date, Utc),
+        url: post.url,
+        word_count: post.word_count,
+        reading_time: post.reading_time,
+        toc: match post.toc {
+            Some(ref entries) => Some(serde_json::from_str(entries)?),
+            None => None,
+        },
+        html: Html::new(post.html.to_string()),
+        tags: tags,
+        categories: categories,
+        next_post: next_post,
+        prev_post: prev_post,
+    })
+}
+
+/// Returns the URL of the post that the given path is an alias of, if any.
+///
+/// Aliases of drafts and posts scheduled for the future are ignored.
+pub fn find_alias(conn: &SqliteConnection, alias: &str) -> errors::Result<Option<String>> {
+    use crate::schema::aliases;
+    use crate::schema::posts::dsl::*;
+
+    let post_url = aliases::table
+        .inner_join(posts)
+        .select(url)
+        .filter(aliases::path.eq(redirects::normalize(alias)))
+        .filter(visibility.ne(Visibility::Draft.as_str()))
+        .filter(date.le(now()))
+        .first(conn)
+        .optional()?;
+
+    Ok(post_url)
+}
+
+/// A link in a blog post that points at a page of the website that does not exist.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrokenLink {
+    /// The name of the file in the blog directory that the post was parsed from.
+    pub file_name: String,
+
+    /// The path that the link points at.
+    pub path: String,
+}
+
+/// Returns the internal links in blog posts that do not point at a post, an alias of a post, or
+/// a page for which `is_page` returns true.
+pub fn broken_links<F>(
+    conn: &SqliteConnection,
+    site_url: &Url,
+    is_page: F,
+) -> errors::Result<Vec<BrokenLink>>
+where
+    F: Fn(&str) -> bool,
+{
+    use crate::schema::aliases;
+    use crate::schema::posts::dsl::*;
+
+    let known_paths = posts
+        .select(url)
+        .load::<String>(conn)?
+        .into_iter()
+        .chain(aliases::table.select(aliases::path).load::<String>(conn)?)
+        .map(|path| redirects::normalize(&path))
+        .collect::<HashSet<_>>();
+
+    let post_html = posts
+        .select((file_name, html))
+        .order(file_name)
+        .load::<(String, String)>(conn)?;
+
+    let mut broken = vec![];
+    for (post_file_name, post_html) in post_html {
+        for link in links::internal_links(&post_html, site_url) {
+            if !known_paths.contains(&redirects::normalize(&link.path)) && !is_page(&link.path) {
+                broken.push(BrokenLink {
+                    file_name: post_file_name.clone(),
+                    path: link.path,
+                });
+            }
+        }
+    }
+
+    Ok(broken)
+}
+
+/// Retrieves a page of blog post summaries from the database, newest first.
+///
+/// Pages are numbered from 1. Requesting a page past the last one is an error, unless it is the
+/// first page of an empty blog.
+pub fn get_summaries(
+    conn: &SqliteConnection,
+    page: i64,
+    per_page: i64,
+) -> errors::Result<SummaryPage> {
+    paginate_summaries(conn, None, page, per_page)
+}
+
+/// Retrieves a page of summaries of the posts written during a year, or a month of that year.
+///
+/// The year and month are interpreted in the given time zone. Archives without any posts are
+/// reported as not found.
+pub fn get_archive(
+    conn: &SqliteConnection,
+    year: i32,
+    month: Option<u32>,
+    timezone: Tz,
+    page: i64,
+    per_page: i64,
+) -> errors::Result<SummaryPage> {
+    let start = NaiveDate::from_ymd_opt(year, month.unwrap_or(1), 1).ok_or(diesel::NotFound)?;
+    let end = match month {
+        Some(12) | None => NaiveDate::from_ymd_opt(year + 1, 1, 1),
+        Some(month) => NaiveDate::from_ymd_opt(year, month + 1, 1),
+    }
+    .ok_or(diesel::NotFound)?;
+
+    let to_utc = |date: NaiveDate| start_of_day(date, timezone).ok_or(diesel::NotFound);
+
+    let summaries = paginate_summaries(conn, Some((to_utc(start)?, to_utc(end)?)), page, per_page)?;
+    if summaries.posts.is_empty() {
+        return Err(diesel::NotFound.into());
+    }
+
+    Ok(summaries)
+}
+
+/// Returns the UTC time at which a day starts in a time zone, if the day exists.
+fn start_of_day(day: NaiveDate, timezone: Tz) -> Option<NaiveDateTime> {
+    timezone
+        .from_local_datetime(&day.and_hms(0, 0, 0))
+        .earliest()
+        .map(|start| start.naive_utc())
+}
+
+/// Returns the listed posts, optionally restricted to those written within a range of UTC times.
+fn summaries_query<'a>(
+    range: Option<(NaiveDateTime, NaiveDateTime)>,
+) -> posts::BoxedQuery<'a, Sqlite> {
+    let mut query = posts::table.filter(listed()).into_boxed();
+
+    if let Some((start, end)) = range {
+        query = query
+            .filter(posts::date.ge(start))
+            .filter(posts::date.lt(end));
+    }
+
+    query
+}
+
+fn paginate_summaries(
+    conn: &SqliteConnection,
+    range: Option<(NaiveDateTime, NaiveDateTime)>,
+    page: i64,
+    per_page: i64,
+) -> errors::Result<SummaryPage> {
+    use crate::schema::posts::dsl::*;
+
+    let total = summaries_query(range).count().get_result::<i64>(conn)?;
+    let total_pages = check_page(page, per_page, total)?;
+
+    let summaries = summaries_query(range)
+        .select((id, title, date, summary, url, word_count, reading_time))
+        .order(date.desc())
+        .limit(per_page)
+        .offset((page - 1) * per_page)
+        .load::<Summary>(conn)?;
+
+    Ok(SummaryPage::new(
+        attach_terms(conn, summaries)?,
+        page,
+        total_pages,
+        total,
+    ))
+}
+
+/// Returns the number of pages needed to show a number of posts, or an error if the requested
+/// page is not one of them.
+///
+/// The first page always exists, even if there are no posts to show.
+fn check_page(page: i64, per_page: i64, total: i64) -> errors::Result<i64> {
+    let total_pages = (total + per_page - 1) / per_page;
+
+    if page < 1 || (page > total_pages && page != 1) {
+        return Err(diesel::NotFound.into());
+    }
+
+    Ok(total_pages)
+}
+
+/// Returns a filter that matches the posts that should appear in summaries, feeds and links
+/// between posts.
+///
+/// Posts that are dated in the future are not listed until that time has passed.
+fn listed() -> Box<dyn BoxableExpression<posts::table, Sqlite, SqlType = Bool>> {
+    Box::new(
+        posts::visibility
+            .eq(Visibility::Public.as_str())
+            .and(posts::date.le(now())),
+    )
+}
+
+/// Returns the current time, for comparison against the dates of posts.
+///
+/// Dates are stored in UTC.
+fn now() -> NaiveDateTime {
+    Utc::now().naive_utc()
+}
+
+#[derive(Debug)]
+struct ParsedPost {
+    metadata: Metadata,
+    content: Markdown,
+
+    /// The time that the post was written, in the configured time zone.
+    date: DateTime<Tz>,
+
+    /// The canonical URL of the post, built from the configured permalink pattern.
+    url: String,
+
+    /// The name of the file in the blog directory that the post was parsed from.
+    file_name: String,
+
+    /// The backend to render the post with, from the configuration.
+    backend: Backend,
+
+    /// The markdown extensions to render the post with, from its metadata or the configuration.
+    extensions: Vec<Extension>,
+}
+
+impl ParsedPost {
+    /// Returns the slug of the post, for use in the URL.
+    ///
+    /// If the metadata does not specify a slug, one is generated from the title.
+    fn slug(&self) -> String {
+        match self.metadata.slug {
+            Some(ref slug) => slug.to_owned(),
+            None => slug::slugify(&self.metadata.title),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Metadata {
+    title: String,
+    #[serde(with = "on_disk_format")]
+    date: PostDate,
+    categories: Vec<String>,
+    tags: Vec<String>,
+    #[serde(default)]
+    visibility: Visibility,
+    #[serde(default)]
+    slug: Option<String>,
+    #[serde(default)]
+    aliases: Vec<String>,
+    #[serde(default)]
+    toc: bool,
+    #[serde(default)]
+    extensions: Option<Vec<Extension>>,
+    #[serde(default)]
+    summary: Option<String>,
+}
+
+/// The date of a post, as written in its metadata.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum PostDate {
+    /// A date with an explicit offset from UTC.
+    Fixed(DateTime<FixedOffset>),
+
+    /// A date without an offset, which is interpreted in the configured time zone.
+    Floating(NaiveDateTime),
+}
+
+impl PostDate {
+    /// Returns the instant that the date refers to in the given time zone, if it exists.
+    fn resolve(self, timezone: Tz) -> Option<DateTime<Tz>> {
+        match self {
+            PostDate::Fixed(date) => Some(date.with_timezone(&timezone)),
+            PostDate::Floating(date) => timezone.from_local_datetime(&date).earliest(),
+        }
+    }
+}
+
+fn parse_post<R>(reader: &mut R, timezone: Tz) -> errors::Result<ParsedPost>
+where
+    R: Read,
+{
+    let post = {
+        let mut post = String::new();
+        reader
+            .read_to_string(&mut post)
+            .chain_err(|| "could not read contents of post")?;
+        post
+    };
+
+    let (metadata, content) = front_matter::parse::<Metadata>(&post)?;
+
+    if let Some(ref slug) = metadata.slug {
+        if !slug::is_valid_slug(slug) {
+            bail!(
+                "invalid slug `{}`, slugs may only contain lowercase letters, digits and dashes",
+                slug
+            );
+        }
+    }
+
+    let date = metadata.date.resolve(timezone).ok_or_else(|| {
+        format!(
+            "the date {:?} does not exist in the {} time zone",
+            metadata.date,
+            timezone.name()
+        )
+    })?;
+
+    Ok(ParsedPost {
+        metadata: metadata,
+        content: Markdown::new(content.to_owned()),
+        date: date,
+        url: String::new(),
+        file_name: String::new(),
+        backend: Backend::default(),
+        extensions: vec![],
+    })
+}
+
+/// Estimates the number of minutes that it takes to read a number of words, rounding up.
+fn reading_time_of(word_count: usize) -> usize {
+    cmp::max(1, (word_count + WORDS_PER_MINUTE - 1) / WORDS_PER_MINUTE)
+}
+
+/// Splits the markdown of a post at the "more" marker, returning the markdown without the marker
+/// and the markdown before it, if the marker is present.
+///
+/// Whitespace inside the marker is ignored, so `<!-- more -->` is recognized as well.
+fn split_summary(markdown: &str) -> (String, Option<String>) {
+    let mut offset = 0;
+
+    for line in markdown.split_inclusive('\n') {
+        let marker = line
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>();
+        if marker == MORE_MARKER {
+            let before = &markdown[..offset];
+            let after = &markdown[offset + line.len()..];
+            return (format!("{}{}", before, after), Some(before.to_owned()));
+        }
+        offset += line.len();
+    }
+
+    (markdown.to_owned(), None)
+}
+
+/// Returns the visible text at the start of some HTML, escaped, and shortened to at most `length`
+/// characters if necessary.
+///
+/// Code blocks are skipped. The text is shortened at the end of the last sentence that fits, as
+/// long as that keeps at least half of the text, and with an ellipsis after the last word that
+/// fits otherwise.
+fn truncate_text(html: &Html, length: usize) -> String {
+    let text = html
+        .prose()
+        .plain_text()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let truncated = if text.chars().count() <= length {
+        text
+    } else {
+        let end = text.char_indices().nth(length).unwrap().0;
+        let fits = &text[..end];
+        let is_boundary = |i: usize| text[i..].starts_with(' ');
+
+        let sentence_end = fits
+            .match_indices(|c| c == '.' || c == '!' || c == '?')
+            .map(|(i, _)| i + 1)
+            .filter(|&i| is_boundary(i))
+            .last()
+            .filter(|&i| i >= end / 2);
+        let word_end = if is_boundary(end) {
+            end
+        } else {
+            fits.rfind(' ').unwrap_or(end)
+        };
+
+        match sentence_end {
+            Some(i) => fits[..i].to_owned(),
+            None => format!(
+                "{}…",
+                fits[..word_end].trim_end_matches(|c: char| !c.is_alphanumeric())
+            ),
+        }
+    };
+
+    truncated
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Finishes the summary of a post by linking to the rest of it.
+///
+/// The summary should end with an open paragraph, which the link is added to.
+fn create_summary(html: &str, url: &str, policy: &sanitize::Policy) -> Html {
+    let summary = format!(r#"{}<a href="{}">Continue&rarr;</a></p>"#, html, url);
+
+    // Sanitize the summary so that any unclosed tags are closed again.
+    policy.clean(&summary)
+}
+
+fn parse_posts<P>(
+    directory: P,
+    config: &Config,
+    shortcodes: &Shortcodes,
+) -> errors::Result<Vec<ParsedPost>>
+where
+    P: AsRef<Path>,
+{
+    let entries = fs::read_dir(directory)
+        .chain_err(|| "could not read blog posts directory")?
+        .into_iter();
+
+    let mut parsed_posts = entries
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| is_post_file(path))
+        .map(|path| parse_post_file(path, config, shortcodes))
+        .collect::<errors::Result<Vec<_>>>()?;
+
+    let targets = link_targets(
+        parsed_posts
+            .iter()
+            .map(|post| (post.metadata.title.clone(), post.slug(), post.url.clone())),
+    );
+    for post in &mut parsed_posts {
+        resolve_links(post, &targets)?;
+    }
+
+    Ok(parsed_posts)
+}
+
+/// Returns the posts that wiki links may point at, by both title and slug.
+///
+/// Each post is given as its title, slug and URL.
+fn link_targets<I>(posts: I) -> HashMap<String, wiki_links::Target>
+where
+    I: IntoIterator<Item = (String, String, String)>,
+{
+    let mut targets = HashMap::new();
+
+    for (title, slug, url) in posts {
+        let target = wiki_links::Target {
+            url,
+            title: title.clone(),
+        };
+        targets.insert(slug, target.clone());
+        targets.insert(title, target);
+    }
+
+    targets
+}
+
+/// Replaces the wiki links in a post with links to the URLs of the posts that they point at.
+fn resolve_links(
+    post: &mut ParsedPost,
+    targets: &HashMap<String, wiki_links::Target>,
+) -> errors::Result<()> {
+    let content = wiki_links::replace(&post.content, |target| targets.get(target).cloned())
+        .map_err(|link| ErrorKind::UnresolvedLink(post.file_name.clone(), link))?;
+    post.content = Markdown::new(content);
+    Ok(())
+}
+
+fn parse_post_file(
+    path: PathBuf,
+    config: &Config,
+    shortcodes: &Shortcodes,
+) -> errors::Result<ParsedPost> {
+    let mut file = File::open(&path).chain_err(|| "error opening directory entry")?;
+    let mut post =
+        parse_post(&mut file, config.timezone).chain_err(|| ErrorKind::PostParse(path.clone()))?;
+    post.content = Markdown::new(
+        shortcodes
+            .expand(&post.content)
+            .chain_err(|| ErrorKind::PostParse(path.clone()))?,
+    );
+    post.url = config.permalink.format(&post.date, &post.slug());
+    post.file_name = file_name_of(&path)?;
+    post.backend = config.markdown_backend;
+    post.extensions = post
+        .metadata
+        .extensions
+        .clone()
+        .unwrap_or_else(|| config.markdown_extensions.clone());
+    Ok(post)
+}
+
+mod on_disk_format {
+    use std::fmt;
+
+    use chrono::{DateTime, NaiveDate, NaiveDateTime};
+    use serde::{self, Deserialize, Deserializer};
+    use toml;
+
+    use super::PostDate;
+
+    /// The date format originally used in the blog posts' markdown files.
+    const LEGACY_FORMAT: &str = "%l:%M%P %m/%d/%y";
+
+    /// ISO 8601 date formats that include an offset from UTC, in addition to RFC 3339.
+    const FIXED_FORMATS: &[&str] = &[
+        "%Y-%m-%d %H:%M:%S%z",
+        "%Y-%m-%d %H:%M%z",
+        "%Y-%m-%dT%H:%M%z",
+    ];
+
+    /// Date formats without an offset from UTC.
+    const FLOATING_FORMATS: &[&str] = &[
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        LEGACY_FORMAT,
+    ];
+
+    /// A date as it appears in YAML or TOML front matter.
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawDate {
+        String(String),
+        Toml(toml::value::Datetime),
+    }
+
+    impl fmt::Display for RawDate {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match *self {
+                RawDate::String(ref date) => date.fmt(f),
+                RawDate::Toml(ref date) => date.fmt(f),
+            }
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<PostDate, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let date = RawDate::deserialize(deserializer)?.to_string();
+        parse(date.trim()).ok_or_else(|| {
+            serde::de::Error::custom(format!(
+                "unrecognized date `{}`, expected an RFC 3339 date such as `2014-05-09T20:39:00-07:00`",
+                date
+            ))
+        })
+    }
+
+    pub fn parse(date: &str) -> Option<PostDate> {
+        if let Ok(date) = DateTime::parse_from_rfc3339(date) {
+            return Some(PostDate::Fixed(date));
+        }
+
+        let fixed = FIXED_FORMATS
+            .iter()
+            .filter_map(|format| DateTime::parse_from_str(date, format).ok())
+            .map(PostDate::Fixed);
+
+        let floating = FLOATING_FORMATS
+            .iter()
+            .filter_map(|format| NaiveDateTime::parse_from_str(date, format).ok())
+            .chain(
+                NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                    .ok()
+                    .map(|date| date.and_hms(0, 0, 0)),
+            )
+            .map(PostDate::Floating);
+
+        fixed.chain(floating).next()
+    }
+}
+
+/// Formatting of dates for display on the website.
+pub mod human_readable_format {
+    use chrono::{DateTime, TimeZone, Utc};
+    use chrono_tz::Tz;
+
+    /// The date format suitable for displaying on the website.
+    pub const HUMAN_READABLE_FORMAT: &str = "%B %e, %Y";
+
+    /// Formats an instant as a human-readable date in the given time zone.
+    pub fn format(date: &DateTime<Utc>, timezone: Tz) -> String {
+        timezone
+            .from_utc_datetime(&date.naive_utc())
+            .format(HUMAN_READABLE_FORMAT)
+            .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use chrono_tz::America::Los_Angeles;
+    use diesel::connection::SimpleConnection;
+    use diesel::prelude::*;
+    use diesel::sqlite::SqliteConnection;
+    use tempdir::TempDir;
+
+    use crate::config::{self, Config};
+    use crate::errors::{Error, ErrorKind};
+    use crate::markdown::{Backend, Html};
+    use crate::shortcodes::Shortcodes;
+
+    use super::{search, Post, PostDate, PostQuery, Taxonomy};
+
+    fn test_config() -> Config {
+        config::load("config.yaml").unwrap()
+    }
+
+    fn test_shortcodes() -> Shortcodes {
+        Shortcodes::load("templates", &[]).unwrap()
+    }
+
+    fn empty_database() -> SqliteConnection {
+        let conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.batch_execute(&fs::read_to_string("schema.sql").unwrap())
+            .unwrap();
+        conn
+    }
+
+    fn load_all_posts() -> SqliteConnection {
+        let conn = empty_database();
+        super::load("blog", &conn, &test_config(), &test_shortcodes()).unwrap();
+        conn
+    }
+
+    /// Reads a post from the blog directory.
+    fn read_post(file_name: &str) -> String {
+        fs::read_to_string(Path::new("blog").join(file_name)).unwrap()
+    }
+
+    /// Writes posts, given as pairs of file names and contents, to a temporary directory.
+    fn write_posts(posts: &[(&str, &str)]) -> TempDir {
+        let directory = TempDir::new("blog").unwrap();
+        for (file_name, post) in posts {
+            fs::write(directory.path().join(file_name), post).unwrap();
+        }
+        directory
+    }
+
+    /// Writes posts to a temporary directory and loads them into an empty database.
+    fn load_posts(posts: &[(&str, &str)], config: &Config) -> (TempDir, SqliteConnection) {
+        let directory = write_posts(posts);
+        let conn = empty_database();
+        super::load(directory.path(), &conn, config, &test_shortcodes()).unwrap();
+        (directory, conn)
+    }
+
+    fn get_post(conn: &SqliteConnection, (year, month, day): (i32, u32, u32), slug: &str) -> Post {
+        let query = PostQuery::on_date(NaiveDate::from_ymd(year, month, day), slug);
+        super::get_post(conn, &query, false, Los_Angeles).unwrap()
+    }
+
+    #[test]
+    fn parse_all_posts() {
+        super::parse_posts("blog", &test_config(), &test_shortcodes()).unwrap();
+    }
+
+    #[test]
+    fn date_formats() {
+        let expected = Utc.ymd(2014, 5, 10).and_hms(3, 39, 0);
+
+        for date in &[
+            "8:39pm 05/09/14",
+            "2014-05-09T20:39:00-07:00",
+            "2014-05-10T03:39:00Z",
+            "2014-05-09 20:39:00-0700",
+            "2014-05-09T20:39",
+            "2014-05-09 20:39",
+        ] {
+            let parsed = super::on_disk_format::parse(date).unwrap();
+            assert_eq!(parsed.resolve(Los_Angeles).unwrap(), expected, "{}", date);
+        }
+
+        assert_eq!(
+            super::on_disk_format::parse("2014-05-09"),
+            Some(PostDate::Floating(
+                NaiveDate::from_ymd(2014, 5, 9).and_hms(0, 0, 0)
+            ))
+        );
+        assert!(super::on_disk_format::parse("05/09/2014").is_none());
+
+        // 2:30am did not occur on the day that daylight saving time began.
+        let skipped = super::on_disk_format::parse("2014-03-09T02:30").unwrap();
+        assert!(skipped.resolve(Los_Angeles).is_none());
+    }
+
+    #[test]
+    fn human_readable_dates() {
+        let date = Utc.ymd(2014, 5, 10).and_hms(3, 39, 0);
+        assert_eq!(
+            super::human_readable_format::format(&date, Los_Angeles),
+            "May  9, 2014"
+        );
+    }
+
+    #[test]
+    fn terms() {
+        let conn = load_all_posts();
+
+        let summaries = super::find_summaries_by_term(&conn, Taxonomy::Tag, "python").unwrap();
+        assert_eq!(summaries.len(), 3);
+        assert!(summaries
+            .iter()
+            .all(|summary| summary.tags.iter().any(|tag| tag.name == "python")));
+
+        let counts = super::get_term_counts(&conn, Taxonomy::Category).unwrap();
+        let open_source = counts
+            .iter()
+            .find(|count| count.name == "open source")
+            .unwrap();
+        assert_eq!(open_source.count, 1);
+        assert_eq!(open_source.url, "/blog/categories/open%20source");
+    }
+
+    #[test]
+    fn visibility() {
+        use crate::schema::posts::dsl::{posts, slug, visibility};
+
+        let conn = load_all_posts();
+
+        diesel::update(posts.filter(slug.eq("patching-vim")))
+            .set(visibility.eq("draft"))
+            .execute(&conn)
+            .unwrap();
+        diesel::update(posts.filter(slug.eq("video-games-and-programming")))
+            .set(visibility.eq("unlisted"))
+            .execute(&conn)
+            .unwrap();
+
+        let summaries = super::get_summaries(&conn, 1, 10).unwrap().posts;
+        assert!(summaries
+            .iter()
+            .all(|summary| summary.title != "Patching Vim"
+                && summary.title != "Video Games and Programming"));
+
+        let draft_date = NaiveDate::from_ymd(2014, 5, 9);
+        assert!(super::get_post(
+            &conn,
+            &PostQuery::on_date(draft_date, "patching-vim"),
+            false,
+            Los_Angeles
+        )
+        .is_err());
+        assert!(super::get_post(
+            &conn,
+            &PostQuery::on_date(draft_date, "patching-vim"),
+            true,
+            Los_Angeles
+        )
+        .is_ok());
+
+        let unlisted_date = NaiveDate::from_ymd(2014, 3, 15);
+        super::get_post(
+            &conn,
+            &PostQuery::on_date(unlisted_date, "video-games-and-programming"),
+            false,
+            Los_Angeles,
+        )
+        .unwrap();
+
+        let last_listed_date = NaiveDate::from_ymd(2014, 1, 14);
+        let last_listed = super::get_post(
+            &conn,
+            &PostQuery::on_date(last_listed_date, "in-defense-of-ti-basic"),
+            false,
+            Los_Angeles,
+        )
+        .unwrap();
+        assert!(last_listed.next_post.is_none());
+    }
+
+    #[test]
+    fn scheduled_posts() {
+        use crate::schema::posts::dsl::{date, posts, slug};
+
+        let conn = load_all_posts();
+
+        let future = NaiveDate::from_ymd(2100, 1, 1).and_hms(20, 0, 0);
+        diesel::update(posts.filter(slug.eq("patching-vim")))
+            .set(date.eq(future))
+            .execute(&conn)
+            .unwrap();
+
+        let summaries = super::get_summaries(&conn, 1, 10).unwrap().posts;
+        assert!(summaries
+            .iter()
+            .all(|summary| summary.title != "Patching Vim"));
+
+        let future_date = future.date();
+        assert!(super::get_post(
+            &conn,
+            &PostQuery::on_date(future_date, "patching-vim"),
+            false,
+            Los_Angeles
+        )
+        .is_err());
+        assert!(super::get_post(
+            &conn,
+            &PostQuery::on_date(future_date, "patching-vim"),
+            true,
+            Los_Angeles
+        )
+        .is_ok());
+
+        let last_published_date = NaiveDate::from_ymd(2014, 3, 15);
+        let last_published = super::get_post(
+            &conn,
+            &PostQuery::on_date(last_published_date, "video-games-and-programming"),
+            false,
+            Los_Angeles,
+        )
+        .unwrap();
+        assert!(last_published.next_post.is_none());
+    }
+
+    #[test]
+    fn compare_renderers() {
+        let config = test_config();
+
+        let differences = super::compare_renderers(
+            "blog/",
+            &config,
+            &test_shortcodes(),
+            Backend::Hoedown,
+            Backend::Hoedown,
+        )
+        .unwrap();
+        assert!(differences.is_empty());
+
+        // Unlike CommonMark, hoedown does not require a space after the `#` of a heading.
+        let file_name = "2012-04-13-blog-is-up-and-running.md";
+        let post = read_post(file_name).replace("Woo hoo!", "Woo hoo!\n\n#Update");
+        let directory = write_posts(&[(file_name, &post)]);
+
+        let differences = super::compare_renderers(
+            directory.path(),
+            &config,
+            &test_shortcodes(),
+            Backend::Hoedown,
+            Backend::CommonMark,
+        )
+        .unwrap();
+        assert_eq!(differences.len(), 1);
+        assert_eq!(differences[0].file_name, file_name);
+
+        let diff = differences[0].diff.lines().collect::<Vec<_>>();
+        assert!(diff
+            .iter()
+            .all(|line| line.starts_with('-') || line.starts_with('+')));
+        assert!(diff
+            .iter()
+            .any(|line| line.starts_with(r#"-<h1 id="update">Update"#)));
+        assert!(diff.contains(&"+<p>#Update</p>"));
+    }
+
+    #[test]
+    fn reload_post() {
+        let file_name = "2014-05-07-patching-vim.md";
+        let config = test_config();
+        let (directory, conn) = load_posts(&[(file_name, &read_post(file_name))], &config);
+        let post_path = directory.path().join(file_name);
+
+        let post = read_post(file_name).replace("title: Patching Vim", "title: Patching Neovim");
+        fs::write(&post_path, post).unwrap();
+        super::reload_post(&post_path, &conn, &config, &test_shortcodes()).unwrap();
+
+        let summaries = super::get_summaries(&conn, 1, 10).unwrap().posts;
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].title, "Patching Neovim");
+        assert_eq!(
+            super::find_summaries(&conn, "editor", &test_config(), 1, 10)
+                .unwrap()
+                .posts
+                .len(),
+            1
+        );
+
+        fs::remove_file(&post_path).unwrap();
+        super::reload_post(&post_path, &conn, &config, &test_shortcodes()).unwrap();
+
+        assert!(super::get_summaries(&conn, 1, 10).unwrap().posts.is_empty());
+        assert!(
+            super::find_summaries(&conn, "editor", &test_config(), 1, 10)
+                .unwrap()
+                .posts
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn wiki_links() {
+        let target = "2014-05-07-patching-vim.md";
+        let linking = "2012-04-13-blog-is-up-and-running.md";
+        let post = read_post(linking).replace(
+            "Woo hoo!",
+            "See [[Patching Vim]] and [[patching-vim|this]].",
+        );
+        let config = test_config();
+        let (directory, conn) =
+            load_posts(&[(target, &read_post(target)), (linking, &post)], &config);
+        let target_path = directory.path().join(target);
+        let linking_path = directory.path().join(linking);
+
+        let post = get_post(&conn, (2012, 4, 13), "blog-is-up-and-running");
+        assert!(post
+            .html
+            .contains(r#"See <a href="/blog/2014/5/9/patching-vim">Patching Vim</a> and <a href="/blog/2014/5/9/patching-vim">this</a>."#));
+
+        let post = fs::read_to_string(&linking_path)
+            .unwrap()
+            .replace("[[Patching Vim]]", "[[Patching Emacs]]");
+        fs::write(&linking_path, post).unwrap();
+        match super::reload_post(&linking_path, &conn, &config, &test_shortcodes()) {
+            Err(Error(ErrorKind::UnresolvedLink(file_name, link), _)) => {
+                assert_eq!(file_name, "2012-04-13-blog-is-up-and-running.md");
+                assert_eq!(link, "[[Patching Emacs]]");
+            }
+            result => panic!("expected an unresolved link, got {:?}", result),
+        }
+
+        // Moving the linked post must update the link in the post that points at it.
+        let post = fs::read_to_string(&linking_path)
+            .unwrap()
+            .replace("[[Patching Emacs]]", "[[Patching Vim]]");
+        fs::write(&linking_path, post).unwrap();
+        let post = fs::read_to_string(&target_path)
+            .unwrap()
+            .replace("05/09/14", "05/10/14");
+        fs::write(&target_path, post).unwrap();
+        super::reload_post(&target_path, &conn, &config, &test_shortcodes()).unwrap();
+
+        let post = get_post(&conn, (2012, 4, 13), "blog-is-up-and-running");
+        assert!(post
+            .html
+            .contains(r#"See <a href="/blog/2014/5/10/patching-vim">Patching Vim</a>"#));
+
+        // Renaming the linked post breaks the link to its title, so the reload is rejected.
+        let post = fs::read_to_string(&target_path)
+            .unwrap()
+            .replace("title: Patching Vim", "title: Patching Neovim");
+        fs::write(&target_path, post).unwrap();
+        match super::reload_post(&target_path, &conn, &config, &test_shortcodes()) {
+            Err(Error(ErrorKind::UnresolvedLink(file_name, link), _)) => {
+                assert_eq!(file_name, "2012-04-13-blog-is-up-and-running.md");
+                assert_eq!(link, "[[Patching Vim]]");
+            }
+            result => panic!("expected an unresolved link, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn search() {
+        let conn = load_all_posts();
+
+        let summaries = super::find_summaries(&conn, "\"open source\"", &test_config(), 1, 10)
+            .unwrap()
+            .posts;
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].title, "Patching Vim");
+
+        assert!(
+            super::find_summaries(&conn, "noopener", &test_config(), 1, 10)
+                .unwrap()
+                .posts
+                .is_empty()
+        );
+
+        let summaries = super::find_summaries(&conn, "vim", &test_config(), 1, 10)
+            .unwrap()
+            .posts;
+        assert!(!summaries.is_empty());
+        assert!(summaries
+            .iter()
+            .all(|summary| summary.summary.to_lowercase().contains("<mark>vim</mark>")));
+    }
+
+    #[test]
+    fn search_ranking() {
+        let conn = load_all_posts();
+
+        let page = super::find_summaries(&conn, "vim", &test_config(), 1, 1).unwrap();
+        assert_eq!(page.posts.len(), 1);
+        assert_eq!(page.posts[0].title, "Patching Vim");
+        assert_eq!(page.total_posts, 2);
+        assert_eq!(page.total_pages, 2);
+        assert_eq!(page.next_page, Some(2));
+
+        let page = super::find_summaries(&conn, "vim", &test_config(), 2, 1).unwrap();
+        assert_eq!(page.posts.len(), 1);
+        assert_ne!(page.posts[0].title, "Patching Vim");
+
+        assert!(super::find_summaries(&conn, "vim", &test_config(), 3, 1).is_err());
+    }
+
+    #[test]
+    fn search_filters() {
+        let conn = load_all_posts();
+        let config = test_config();
+        let titles = |query| {
+            super::find_summaries(&conn, query, &config, 1, 100)
+                .unwrap()
+                .posts
+                .into_iter()
+                .map(|summary| summary.title)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(titles("title:vim"), &["Patching Vim"]);
+        assert_eq!(titles("vim -title:vim"), &["Summer Coming to a Close"]);
+        assert_eq!(titles("vim before:2014"), &["Summer Coming to a Close"]);
+        assert_eq!(titles("vim after:2014-05-07"), &["Patching Vim"]);
+        assert!(titles("vim after:2014-05-10").is_empty());
+
+        let programming = titles("category:programming");
+        assert!(programming.contains(&String::from("Patching Vim")));
+        assert!(!titles("category:programming -\"open source\"")
+            .contains(&String::from("Patching Vim")));
+        assert!(!titles("-category:programming").contains(&String::from("Patching Vim")));
+
+        let summaries =
+            super::find_summaries(&conn, "category:\"open source\"", &test_config(), 1, 10)
+                .unwrap()
+                .posts;
+        assert_eq!(summaries.len(), 1);
+        assert!(!summaries[0].summary.contains("<mark>"));
+    }
+
+    #[test]
+    fn search_tokenizers() {
+        let patching_vim = "2014-05-07-patching-vim.md";
+        let first_post = "2012-04-13-blog-is-up-and-running.md";
+        let post = read_post(first_post).replace("Woo hoo!", "Written at a café.");
+        let mut config = test_config();
+        config.search.tokenizer = search::Tokenizer::Simple;
+        let (_directory, conn) = load_posts(
+            &[
+                (patching_vim, &read_post(patching_vim)),
+                (first_post, &post),
+            ],
+            &config,
+        );
+
+        let count = |config: &Config, query| {
+            super::find_summaries(&conn, query, config, 1, 10)
+                .unwrap()
+                .posts
+                .len()
+        };
+        assert_eq!(count(&config, "patches"), 0);
+        assert_eq!(count(&config, "cafe"), 0);
+
+        config.search.tokenizer = search::Tokenizer::Porter;
+        super::create_fts_index(&conn, &config.search).unwrap();
+        assert_eq!(count(&config, "patches"), 1);
+
+        config.search.tokenizer = search::Tokenizer::Unicode61;
+        super::create_fts_index(&conn, &config.search).unwrap();
+        assert_eq!(count(&config, "cafe"), 1);
+        assert_eq!(count(&config, "CAFÉ"), 1);
+    }
+
+    #[test]
+    fn invalid_search_queries() {
+        let conn = load_all_posts();
+
+        for query in &["\"vim", "AND", "vim OR", "(", "*", "-", "\"\""] {
+            super::find_summaries(&conn, query, &test_config(), 1, 10).unwrap();
+        }
+
+        assert!(
+            !super::find_summaries(&conn, "\"vim", &test_config(), 1, 10)
+                .unwrap()
+                .posts
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn slug_collisions() {
+        let post = read_post("2014-05-07-patching-vim.md");
+        let directory = write_posts(&[
+            ("2014-05-07-patching-vim.md", &post),
+            ("2014-05-07-patching-vim-again.md", &post),
+        ]);
+        let duplicate_path = directory.path().join("2014-05-07-patching-vim-again.md");
+
+        let conn = empty_database();
+        let config = test_config();
+        match super::load(directory.path(), &conn, &config, &test_shortcodes()) {
+            Err(Error(ErrorKind::SlugCollision(url, _, _), _)) => {
+                assert_eq!(url, "/blog/2014/5/9/patching-vim")
+            }
+            result => panic!("expected a slug collision, got {:?}", result),
+        }
+
+        let post = post.replace(
+            "title: Patching Vim",
+            "title: Patching Vim\nslug: patching-vim-again",
+        );
+        fs::write(&duplicate_path, post).unwrap();
+        super::load(directory.path(), &conn, &config, &test_shortcodes()).unwrap();
+
+        let post = get_post(&conn, (2014, 5, 9), "patching-vim-again");
+        assert_eq!(post.title, "Patching Vim");
+    }
+
+    #[test]
+    fn pagination() {
+        let conn = load_all_posts();
+
+        let first = super::get_summaries(&conn, 1, 4).unwrap();
+        assert_eq!(first.posts.len(), 4);
+        assert_eq!(first.posts[0].title, "Patching Vim");
+        assert_eq!(first.prev_page, None);
+        assert_eq!(first.next_page, Some(2));
+
+        let last = super::get_summaries(&conn, first.total_pages, 4).unwrap();
+        assert!(!last.posts.is_empty());
+        assert_eq!(last.posts.last().unwrap().title, "Blog is Up and Running!");
+        assert_eq!(last.next_page, None);
+
+        assert!(super::get_summaries(&conn, 0, 4).is_err());
+        assert!(super::get_summaries(&conn, first.total_pages + 1, 4).is_err());
+    }
+
+    #[test]
+    fn archives() {
+        let conn = load_all_posts();
+
+        let year = super::get_archive(&conn, 2014, None, Los_Angeles, 1, 10).unwrap();
+        let titles = year
+            .posts
+            .iter()
+            .map(|post| &*post.title)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            titles,
+            &[
+                "Patching Vim",
+                "Video Games and Programming",
+                "In Defense of TI-Basic"
+            ]
+        );
+
+        let month = super::get_archive(&conn, 2014, Some(5), Los_Angeles, 1, 10).unwrap();
+        assert_eq!(month.posts.len(), 1);
+
+        assert!(super::get_archive(&conn, 2014, Some(2), Los_Angeles, 1, 10).is_err());
+        assert!(super::get_archive(&conn, 2014, Some(13), Los_Angeles, 1, 10).is_err());
+    }
+
+    #[test]
+    fn reading_time() {
+        assert_eq!(super::reading_time_of(0), 1);
+        assert_eq!(super::reading_time_of(200), 1);
+        assert_eq!(super::reading_time_of(201), 2);
+        assert_eq!(super::reading_time_of(1400), 7);
+
+        let post = get_post(&load_all_posts(), (2014, 5, 9), "patching-vim");
+        assert!(post.word_count > 0);
+        assert_eq!(
+            post.reading_time as usize,
+            super::reading_time_of(post.word_count as usize)
+        );
+    }
+
+    #[test]
+    fn word_count_without_code() {
+        let file_name = "2012-04-13-blog-is-up-and-running.md";
+        let word_count = |post: &str| {
+            let (_directory, conn) = load_posts(&[(file_name, post)], &test_config());
+            get_post(&conn, (2012, 4, 13), "blog-is-up-and-running").word_count
+        };
+
+        let post = read_post(file_name);
+        let with_code = post.replace(
+            "Woo hoo!",
+            "Woo hoo!\n\n```rust\nfn main() {\n    println!(\"Hello, world!\");\n}\n```\n",
+        );
+
+        assert!(word_count(&post) > 0);
+        assert_eq!(word_count(&with_code), word_count(&post));
+    }
+
+    #[test]
+    fn table_of_contents() {
+        let file_name = "2014-05-07-patching-vim.md";
+        let post = read_post(file_name)
+            .replace("tags: []", "tags: []\ntoc: true")
+            .replace("As those familiar", "## Background\n\nAs those familiar");
+        let (_directory, conn) = load_posts(&[(file_name, &post)], &test_config());
+
+        let post = get_post(&conn, (2014, 5, 9), "patching-vim");
+        assert!(post.html.contains(r#"<h2 id="background">"#));
+
+        let toc = post.toc.unwrap();
+        assert_eq!(toc.len(), 1);
+        assert_eq!(toc[0].id, "background");
+        assert_eq!(toc[0].title, "Background");
+    }
+
+    #[test]
+    fn summary_sanitization() {
+        let text = "<p>Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do \
+                    eiusmod tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim \
+                    veniam, quis nostrud exercitation ullamco laboris nisi ut aliquip ex ea \
+                    commodo consequat. Duis aute irure dolor in reprehenderit in voluptate velit \
+                    esse cillum dolore eu fugiat nulla pariatur. Excepteur sint occaecat \
+                    cupidatat non proident, sunt in culpa qui officia deserunt mollit anim id est \
+                    laborum.</p>";
+        let html = text.chars().take(super::SUMMARY_LENGTH).collect::<String>();
+
+        let summary = super::create_summary(&html, "http://google.com", &Default::default());
+
+        assert!(summary.ends_with("</p>"));
+    }
+
+    #[test]
+    fn summary_truncation() {
+        let html = Html::new(String::from(
+            "<pre><code>fn main() {}</code></pre><p>Vim &amp; Emacs are editors. \
+             I use <a href=\"https://www.vim.org\">Vim</a> every day, almost.</p>",
+        ));
+
+        assert_eq!(
+            super::truncate_text(&html, 100),
+            "Vim &amp; Emacs are editors. I use Vim every day, almost."
+        );
+        assert_eq!(
+            super::truncate_text(&html, 40),
+            "Vim &amp; Emacs are editors."
+        );
+        assert_eq!(super::truncate_text(&html, 20), "Vim &amp; Emacs are…");
+        assert_eq!(
+            super::truncate_text(&html, 24),
+            "Vim &amp; Emacs are editors."
+        );
+    }
+
+    #[test]
+    fn more_marker() {
+        let (content, summary) = super::split_summary("Intro.\n\n<!-- more -->\n\nThe rest.\n");
+        assert_eq!(content, "Intro.\n\n\nThe rest.\n");
+        assert_eq!(summary.as_ref().map(String::as_str), Some("Intro.\n\n"));
+
+        let (content, summary) = super::split_summary("No marker `<!-- more -->`.\n");
+        assert_eq!(content, "No marker `<!-- more -->`.\n");
+        assert_eq!(summary, None);
     }
 }
@@ -0,0 +1,413 @@
+//! The query language of the blog search box.
+//!
+//! A query is a list of whitespace-separated clauses, all of which must match a post:
+//!
+//! ```text
+//! title:vim tag:programming after:2013-01-01 before:2014 "exact phrase" -scala
+//! ```
+//!
+//! - A word or a quoted phrase matches posts that contain it anywhere, including in their title,
+//!   tags and categories.
+//! - `title:` matches posts whose title contains a word or quoted phrase.
+//! - `tag:` and `category:` match posts filed under a tag or category, by its exact name.
+//! - `after:` and `before:` match posts written on or after, or before, the start of a year, month
+//!   or day, written as `2014`, `2014-05` or `2014-05-09`.
+//!
+//! Any clause except a date may be negated with a leading `-` to exclude the posts that it
+//! matches. A negated date is the opposite date: `-after:2014` is the same as `before:2014`.
+//!
+//! Search syntax never causes an error. Anything that cannot be parsed as a filter is searched for
+//! as text instead, and characters with a special meaning in SQLite full text queries are ignored.
+//!
+//! How words are matched depends on the configured [`Options`]. With the `porter` tokenizer, a
+//! search for "patch" also matches "patching". With the `unicode61` tokenizer, case is folded for
+//! all of Unicode, and accented letters may match their unaccented forms.
+//!
+//! [`Options`]: struct.Options.html
+
+use std::collections::BTreeSet;
+
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+use super::Taxonomy;
+
+/// How posts are indexed and searched.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Options {
+    /// The tokenizer that splits the text of posts and queries into words.
+    pub tokenizer: Tokenizer,
+
+    /// Whether the `unicode61` tokenizer matches letters regardless of their diacritics, so that
+    /// "cafe" matches "café".
+    pub remove_diacritics: bool,
+
+    /// Words that are too common to be worth searching for, such as "the". They are ignored in
+    /// queries, unless the query contains nothing else.
+    pub stop_words: BTreeSet<String>,
+}
+
+impl Options {
+    /// Returns the argument that configures the tokenizer of an FTS4 table.
+    pub fn tokenize_argument(&self) -> String {
+        match self.tokenizer {
+            Tokenizer::Simple => String::from("tokenize=simple"),
+            Tokenizer::Porter => String::from("tokenize=porter"),
+            Tokenizer::Unicode61 => format!(
+                r#"tokenize=unicode61 "remove_diacritics={}""#,
+                if self.remove_diacritics { 1 } else { 0 }
+            ),
+        }
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            tokenizer: Tokenizer::default(),
+            remove_diacritics: true,
+            stop_words: BTreeSet::new(),
+        }
+    }
+}
+
+/// The SQLite full text search tokenizers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tokenizer {
+    /// Splits text on characters other than ASCII letters and digits, and folds ASCII case.
+    Simple,
+
+    /// Like `simple`, but reduces English words to their stems.
+    Porter,
+
+    /// Splits text according to Unicode, and folds case for all of it.
+    Unicode61,
+}
+
+impl Default for Tokenizer {
+    fn default() -> Self {
+        Tokenizer::Simple
+    }
+}
+
+/// A parsed search query.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Query {
+    /// The clauses that a post must match, in the order they were written.
+    pub clauses: Vec<Clause>,
+}
+
+/// A single condition on the posts that match a query.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Clause {
+    /// Text that must appear in a post, or must not if it is negated.
+    Text {
+        /// The part of the post that the text is searched for in.
+        field: Field,
+        /// A word or phrase.
+        text: String,
+        /// Whether posts containing the text are excluded.
+        negated: bool,
+    },
+
+    /// A tag or category that a post must be filed under, or must not if it is negated.
+    Term {
+        /// Whether the term is a tag or category.
+        taxonomy: Taxonomy,
+        /// The name of the term.
+        name: String,
+        /// Whether posts filed under the term are excluded.
+        negated: bool,
+    },
+
+    /// The post must be written on or after the start of a day.
+    After(NaiveDate),
+
+    /// The post must be written before the start of a day.
+    Before(NaiveDate),
+}
+
+/// The parts of a post that text may be searched for in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Field {
+    /// The title, content, tags and categories of the post.
+    Any,
+
+    /// The title of the post.
+    Title,
+}
+
+impl Query {
+    /// Parses a query typed into the search box.
+    pub fn parse(query: &str) -> Query {
+        let mut clauses = vec![];
+        let mut rest = query.trim_start();
+
+        while !rest.is_empty() {
+            let (negated, token) = match rest.strip_prefix('-') {
+                Some(token) => (true, token),
+                None => (false, rest),
+            };
+
+            let (clause, remaining) = match token.find(':') {
+                Some(colon) if !token[..colon].contains(char::is_whitespace) => {
+                    let (value, remaining) = take_value(&token[colon + 1..]);
+                    match filter(&token[..colon], &value, negated) {
+                        Some(clause) => (Some(clause), remaining),
+                        None => text(token, negated),
+                    }
+                }
+                _ => text(token, negated),
+            };
+
+            clauses.extend(clause);
+            rest = remaining.trim_start();
+        }
+
+        Query { clauses }
+    }
+
+    /// Removes words that are stop words, regardless of case, unless they are the only text that
+    /// posts must contain. Phrases are left alone.
+    pub fn remove_stop_words(&mut self, stop_words: &BTreeSet<String>) {
+        let is_stop_word = |clause: &Clause| match *clause {
+            Clause::Text { ref text, .. } => {
+                !text.contains(char::is_whitespace) && stop_words.contains(&text.to_lowercase())
+            }
+            _ => false,
+        };
+        let is_required_text = |clause: &Clause| match *clause {
+            Clause::Text { negated, .. } => !negated,
+            _ => false,
+        };
+
+        if self
+            .clauses
+            .iter()
+            .any(|clause| is_required_text(clause) && !is_stop_word(clause))
+        {
+            self.clauses.retain(|clause| !is_stop_word(clause));
+        } else {
+            self.clauses
+                .retain(|clause| is_required_text(clause) || !is_stop_word(clause));
+        }
+    }
+
+    /// Returns the full text query for the text that posts must contain, if there is any.
+    pub fn fts_query(&self) -> Option<String> {
+        let terms = self
+            .clauses
+            .iter()
+            .filter_map(|clause| match *clause {
+                Clause::Text {
+                    field,
+                    ref text,
+                    negated: false,
+                } => Some(fts_text(field, text)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        if terms.is_empty() {
+            None
+        } else {
+            Some(terms.join(" "))
+        }
+    }
+}
+
+/// Returns the full text query that matches a single word or phrase.
+///
+/// The text is quoted, so that it is never interpreted as query syntax. Full text queries cannot
+/// restrict a quoted phrase to a column, so a phrase in the title matches titles that contain each
+/// of its words instead. Those words are lowercased, so that none of them is an operator.
+pub fn fts_text(field: Field, text: &str) -> String {
+    match field {
+        Field::Any => format!("\"{}\"", text.replace('"', "")),
+        Field::Title => text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| format!("title:{}", word.to_lowercase()))
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+/// Parses the text at the start of a query as a word or quoted phrase.
+fn text(query: &str, negated: bool) -> (Option<Clause>, &str) {
+    let (text, rest) = take_value(query);
+    let clause = if text.chars().any(char::is_alphanumeric) {
+        Some(Clause::Text {
+            field: Field::Any,
+            text,
+            negated,
+        })
+    } else {
+        None
+    };
+    (clause, rest)
+}
+
+/// Parses a filter of the form `name:value`, returning `None` if it is not a valid filter.
+fn filter(name: &str, value: &str, negated: bool) -> Option<Clause> {
+    if value.is_empty() {
+        return None;
+    }
+
+    let clause = match name {
+        "title" if value.chars().any(char::is_alphanumeric) => Clause::Text {
+            field: Field::Title,
+            text: value.to_owned(),
+            negated,
+        },
+        "tag" | "category" => Clause::Term {
+            taxonomy: if name == "tag" {
+                Taxonomy::Tag
+            } else {
+                Taxonomy::Category
+            },
+            name: value.to_owned(),
+            negated,
+        },
+        "after" | "before" => {
+            let date = parse_date(value)?;
+            if (name == "after") != negated {
+                Clause::After(date)
+            } else {
+                Clause::Before(date)
+            }
+        }
+        _ => return None,
+    };
+
+    Some(clause)
+}
+
+/// Splits a word or quoted phrase from the start of a query, returning it without quotes along
+/// with the rest of the query. An unterminated quote extends to the end of the query.
+fn take_value(query: &str) -> (String, &str) {
+    match query.strip_prefix('"') {
+        Some(quoted) => match quoted.find('"') {
+            Some(end) => (quoted[..end].to_owned(), &quoted[end + 1..]),
+            None => (quoted.to_owned(), ""),
+        },
+        None => {
+            let end = query
+                .find(char::is_whitespace)
+                .unwrap_or_else(|| query.len());
+            (query[..end].replace('"', ""), &query[end..])
+        }
+    }
+}
+
+/// Parses the start of a year, month or day.
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    let mut parts = date.splitn(3, '-').map(|part| part.parse::<u32>().ok());
+    let year = parts.next()?? as i32;
+    let month = parts.next().unwrap_or(Some(1))?;
+    let day = parts.next().unwrap_or(Some(1))?;
+    NaiveDate::from_ymd_opt(year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{Clause, Field, Query};
+    use crate::blog::Taxonomy;
+
+    #[test]
+    fn parse() {
+        let query = Query::parse(
+            r#"title:vim tag:programming after:2013-01-01 before:2014 "exact phrase" -scala"#,
+        );
+        assert_eq!(
+            query.clauses,
+            vec![
+                Clause::Text {
+                    field: Field::Title,
+                    text: String::from("vim"),
+                    negated: false,
+                },
+                Clause::Term {
+                    taxonomy: Taxonomy::Tag,
+                    name: String::from("programming"),
+                    negated: false,
+                },
+                Clause::After(NaiveDate::from_ymd(2013, 1, 1)),
+                Clause::Before(NaiveDate::from_ymd(2014, 1, 1)),
+                Clause::Text {
+                    field: Field::Any,
+                    text: String::from("exact phrase"),
+                    negated: false,
+                },
+                Clause::Text {
+                    field: Field::Any,
+                    text: String::from("scala"),
+                    negated: true,
+                },
+            ]
+        );
+        assert_eq!(query.fts_query().unwrap(), r#"title:vim "exact phrase""#);
+    }
+
+    #[test]
+    fn stop_words() {
+        let stop_words = vec![String::from("the"), String::from("a")]
+            .into_iter()
+            .collect();
+
+        let mut query = Query::parse(r#"The vim -a "the editor""#);
+        query.remove_stop_words(&stop_words);
+        assert_eq!(query.fts_query().unwrap(), r#""vim" "the editor""#);
+        assert_eq!(query.clauses.len(), 2);
+
+        let mut query = Query::parse("the -a");
+        query.remove_stop_words(&stop_words);
+        assert_eq!(query.fts_query().unwrap(), r#""the""#);
+        assert_eq!(query.clauses.len(), 1);
+    }
+
+    #[test]
+    fn title_phrases() {
+        let query = Query::parse(r#"title:"Patching OR Vim*""#);
+        assert_eq!(
+            query.fts_query().unwrap(),
+            "title:patching title:or title:vim"
+        );
+    }
+
+    #[test]
+    fn parse_invalid() {
+        let query = Query::parse(r#"after:yesterday -before:2014-05 category:"open source" "vim"#);
+        assert_eq!(
+            query.clauses,
+            vec![
+                Clause::Text {
+                    field: Field::Any,
+                    text: String::from("after:yesterday"),
+                    negated: false,
+                },
+                Clause::After(NaiveDate::from_ymd(2014, 5, 1)),
+                Clause::Term {
+                    taxonomy: Taxonomy::Category,
+                    name: String::from("open source"),
+                    negated: false,
+                },
+                Clause::Text {
+                    field: Field::Any,
+                    text: String::from("vim"),
+                    negated: false,
+                },
+            ]
+        );
+
+        assert_eq!(
+            Query::parse(r#"AND ( * - "" tag:"#).fts_query().unwrap(),
+            r#""AND" "tag:""#
+        );
+        assert_eq!(Query::parse("* -").clauses, vec![]);
+    }
+}
@@ -7,20 +7,83 @@ use std::fs::File;
 use std::io::prelude::*;
 use std::path::Path;
 
+use chrono_tz::Tz;
 use log::*;
 use serde::Deserialize;
 use serde_yaml;
 use url::Url;
 use url_serde;
 
+use crate::blog::search;
 use crate::errors::*;
+use crate::highlight;
+use crate::links::LinkPolicy;
+use crate::markdown::{self, Backend, Extension};
+use crate::permalink::Permalink;
+use crate::sanitize;
 
 /// Configuration values for the website.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct Config {
+    /// The root URL that the website is served from, used to build absolute links.
+    #[serde(with = "url_serde")]
+    pub site_url: Url,
+
+    /// The time zone that blog posts are written in, such as `America/Los_Angeles`.
+    ///
+    /// Post dates without an explicit offset are interpreted in this time zone, and all dates are
+    /// displayed in it.
+    pub timezone: Tz,
+
+    /// The pattern used to build the URLs of blog posts, such as `/blog/:year/:month/:slug`.
+    ///
+    /// Posts are only served from the URL built from this pattern. Changing it breaks links to the
+    /// old URLs unless a redirect is added for them.
+    #[serde(default)]
+    pub permalink: Permalink,
+
+    /// The name of the color scheme used for syntax highlighting, such as `base16-ocean.dark`.
+    #[serde(default = "default_highlight_theme")]
+    pub highlight_theme: String,
+
+    /// The library used to render markdown, either `hoedown` or `commonmark`.
+    #[serde(default)]
+    pub markdown_backend: Backend,
+
+    /// The markdown extensions that are enabled when rendering posts and project descriptions.
+    ///
+    /// Individual posts may override this set with the `extensions` key in their front matter.
+    #[serde(default = "default_markdown_extensions")]
+    pub markdown_extensions: Vec<Extension>,
+
+    /// The rules for sanitizing rendered markdown.
+    #[serde(default)]
+    pub sanitize: sanitize::Policy,
+
+    /// The rules for decorating links in rendered markdown.
+    #[serde(default)]
+    pub links: LinkPolicy,
+
+    /// How blog posts are indexed and searched.
+    #[serde(default)]
+    pub search: search::Options,
+
     /// A link to a PDF copy of my Resume.
     #[serde(with = "url_serde")]
     pub resume_link: Url,
+
+    /// A secret that allows drafts to be viewed when passed as the `preview` query parameter.
+    ///
+    /// If this is not set, drafts cannot be viewed at all.
+    pub preview_token: Option<String>,
+}
+
+fn default_highlight_theme() -> String {
+    String::from(highlight::DEFAULT_THEME)
+}
+
+fn default_markdown_extensions() -> Vec<Extension> {
+    markdown::DEFAULT_EXTENSIONS.to_vec()
 }
 
 fn parse_config<R>(reader: R) -> Result<Config>
@@ -53,11 +116,23 @@ mod tests {
         let test_config = String::from(
             r#"
 ---
+site_url: https://acrussell.com
+timezone: America/Los_Angeles
 resume_link: http://google.com
 "#,
         );
         let expected_config = Config {
+            site_url: Url::parse("https://acrussell.com").unwrap(),
+            timezone: chrono_tz::America::Los_Angeles,
+            permalink: Permalink::default(),
+            highlight_theme: String::from("base16-ocean.dark"),
+            markdown_backend: Backend::Hoedown,
+            markdown_extensions: markdown::DEFAULT_EXTENSIONS.to_vec(),
+            sanitize: Default::default(),
+            links: LinkPolicy::default(),
+            search: search::Options::default(),
             resume_link: Url::parse("http://google.com").unwrap(),
+            preview_token: None,
         };
         assert_eq!(
             expected_config,
@@ -6,9 +6,13 @@ use std::io;
 use std::path::PathBuf;
 
 use diesel;
+use handlebars_iron::handlebars;
 use hubcaps;
 use iron;
+use notify;
+use serde_json;
 use serde_yaml;
+use toml;
 use url;
 
 error_chain! {
@@ -17,13 +21,33 @@ error_chain! {
             description("could not parse blog post"),
             display("could not parse blog post: {}", p.display())
         }
+
+        /// Two posts would be served from the same URL.
+        SlugCollision(url: String, existing: String, duplicate: String) {
+            description("two blog posts have the same URL"),
+            display("{} and {} would both be served from {}, set a distinct `slug` in one of them",
+                    existing, duplicate, url)
+        }
+
+        /// A wiki link in a blog post does not point at any post.
+        UnresolvedLink(file_name: String, link: String) {
+            description("could not resolve a link in a blog post"),
+            display("could not resolve {} in blog post {}, expected the title or slug of a post",
+                    link, file_name)
+        }
     }
 
     foreign_links {
         GitHub(hubcaps::Error);
+        HandlebarsRender(handlebars::RenderError);
+        HandlebarsTemplate(handlebars::TemplateError);
+        HandlebarsTemplateFile(handlebars::TemplateFileError);
         Io(io::Error);
         HTTP(iron::error::HttpError);
+        Json(serde_json::Error);
+        Notify(notify::Error);
         Sql(diesel::result::Error);
+        Toml(toml::de::Error);
         UrlParse(url::ParseError);
         Yaml(serde_yaml::Error);
     }
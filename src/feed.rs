@@ -0,0 +1,220 @@
+//! Syndication feeds for the blog.
+//!
+//! Feeds are rendered by the `feed_atom` and `feed_rss` templates. Since feed readers may fetch
+//! the feed from anywhere, every link in a feed must be absolute, including the links and images
+//! in the posts themselves.
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Serialize;
+use url::Url;
+
+use crate::errors::*;
+use crate::links;
+use crate::models::FeedEntry;
+
+/// The maximum number of posts included in a feed.
+pub const FEED_LENGTH: i64 = 20;
+
+/// The RFC 2822 date format. Unlike `DateTime::to_rfc2822`, this pads the day with a zero.
+const RFC2822_FORMAT: &str = "%a, %d %b %Y %H:%M:%S %z";
+
+/// The syndication formats that feeds are available in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    /// [RFC 4287](https://tools.ietf.org/html/rfc4287) Atom.
+    Atom,
+
+    /// [RSS 2.0](https://cyber.harvard.edu/rss/rss.html).
+    Rss,
+}
+
+impl Format {
+    /// The file extension of feeds in this format, which is also used in the feed's path.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Atom => "atom",
+            Format::Rss => "rss",
+        }
+    }
+
+    /// The name of the template that renders feeds in this format.
+    pub fn template(self) -> &'static str {
+        match self {
+            Format::Atom => "feed_atom",
+            Format::Rss => "feed_rss",
+        }
+    }
+
+    /// The media type of feeds in this format.
+    pub fn media_type(self) -> &'static str {
+        match self {
+            Format::Atom => "application/atom+xml",
+            Format::Rss => "application/rss+xml",
+        }
+    }
+}
+
+/// A feed of blog posts, ready for rendering.
+#[derive(Debug, Serialize)]
+pub struct Feed {
+    /// The title of the feed.
+    pub title: String,
+
+    /// The absolute URL of the feed itself.
+    pub url: String,
+
+    /// The absolute URL of the HTML page that the feed syndicates.
+    pub alternate_url: String,
+
+    /// The root URL of the website, used to resolve relative links in post content.
+    pub site_url: String,
+
+    /// The time that the most recent entry was posted.
+    pub updated: Timestamp,
+
+    /// The posts in the feed, newest first.
+    pub entries: Vec<Entry>,
+}
+
+impl Feed {
+    /// Creates a feed syndicating the HTML page at `path`.
+    ///
+    /// The feed itself is expected to be served from `{path}/feed.{extension}`.
+    pub fn new(
+        site_url: &Url,
+        title: &str,
+        path: &str,
+        format: Format,
+        entries: Vec<FeedEntry>,
+    ) -> Result<Feed> {
+        let alternate_url = site_url.join(path)?;
+        let url = site_url.join(&format!("{}/feed.{}", path, format.extension()))?;
+
+        let updated = entries
+            .iter()
+            .map(|entry| entry.date)
+            .max()
+            .unwrap_or_else(|| Utc::now().naive_utc());
+
+        let entries = entries
+            .into_iter()
+            .map(|entry| {
+                let url = site_url.join(&entry.url)?;
+                Ok(Entry {
+                    title: entry.title,
+                    published: Timestamp::new(entry.date),
+                    summary: links::absolutize(&entry.summary, &url).to_string(),
+                    html: links::absolutize(&entry.html, &url).to_string(),
+                    url: url.to_string(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Feed {
+            title: title.to_owned(),
+            url: url.to_string(),
+            alternate_url: alternate_url.to_string(),
+            site_url: site_url.to_string(),
+            updated: Timestamp::new(updated),
+            entries,
+        })
+    }
+}
+
+/// A single post in a feed.
+#[derive(Debug, Serialize)]
+pub struct Entry {
+    /// The title of the post.
+    pub title: String,
+
+    /// The absolute URL of the post.
+    pub url: String,
+
+    /// The time that the post was written.
+    pub published: Timestamp,
+
+    /// A short preview of the post, as HTML with absolute links.
+    pub summary: String,
+
+    /// The full post, as HTML with absolute links.
+    pub html: String,
+}
+
+/// A point in time, formatted for each of the feed formats.
+#[derive(Debug, Serialize)]
+pub struct Timestamp {
+    /// The time formatted according to RFC 3339, as required by Atom.
+    pub rfc3339: String,
+
+    /// The time formatted according to RFC 2822, as required by RSS.
+    pub rfc2822: String,
+}
+
+impl Timestamp {
+    fn new(date: NaiveDateTime) -> Timestamp {
+        let date = DateTime::<Utc>::from_utc(date, Utc);
+
+        Timestamp {
+            rfc3339: date.to_rfc3339(),
+            rfc2822: date.format(RFC2822_FORMAT).to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use url::Url;
+
+    use crate::models::FeedEntry;
+
+    use super::{Feed, Format};
+
+    #[test]
+    fn absolute_urls() {
+        let site_url = Url::parse("https://acrussell.com").unwrap();
+        let entries = vec![FeedEntry {
+            title: String::from("Patching Vim"),
+            date: NaiveDate::from_ymd(2014, 5, 9).and_hms(20, 39, 0),
+            html: String::from("<p>Vim</p>"),
+            summary: String::from("<p>Vim</p>"),
+            url: String::from("/blog/2014/5/9/patching-vim"),
+        }];
+
+        let feed = Feed::new(&site_url, "Vim", "/blog/tags/vim", Format::Atom, entries).unwrap();
+
+        assert_eq!(feed.url, "https://acrussell.com/blog/tags/vim/feed.atom");
+        assert_eq!(feed.alternate_url, "https://acrussell.com/blog/tags/vim");
+        assert_eq!(
+            feed.entries[0].url,
+            "https://acrussell.com/blog/2014/5/9/patching-vim"
+        );
+        assert_eq!(feed.updated.rfc3339, "2014-05-09T20:39:00+00:00");
+        assert_eq!(feed.updated.rfc2822, "Fri, 09 May 2014 20:39:00 +0000");
+    }
+
+    #[test]
+    fn absolute_content_links() {
+        let site_url = Url::parse("https://acrussell.com").unwrap();
+        let entries = vec![FeedEntry {
+            title: String::from("Patching Vim"),
+            date: NaiveDate::from_ymd(2014, 5, 9).and_hms(20, 39, 0),
+            html: String::from(
+                r##"<p><a href="/blog/tags/vim?page=2&amp;q=x">Vim</a> <a href="#fn1">1</a> <img src="vim.png" alt="Vim"> <a href="https://vim.org">vim.org</a></p>"##,
+            ),
+            summary: String::from(r#"<p><a href="/about">Me</a></p>"#),
+            url: String::from("/blog/2014/5/9/patching-vim"),
+        }];
+
+        let feed = Feed::new(&site_url, "Blog", "/blog", Format::Rss, entries).unwrap();
+
+        assert_eq!(
+            feed.entries[0].html,
+            r#"<p><a href="https://acrussell.com/blog/tags/vim?page=2&amp;q=x">Vim</a> <a href="https://acrussell.com/blog/2014/5/9/patching-vim#fn1">1</a> <img src="https://acrussell.com/blog/2014/5/9/vim.png" alt="Vim"> <a href="https://vim.org/">vim.org</a></p>"#
+        );
+        assert_eq!(
+            feed.entries[0].summary,
+            r#"<p><a href="https://acrussell.com/about">Me</a></p>"#
+        );
+    }
+}
@@ -0,0 +1,162 @@
+//! Parsing of the metadata at the beginning of each blog post.
+//!
+//! Three formats of front matter are recognized:
+//!
+//! - YAML, opened and closed by `---` lines (the closing line may also be `...`).
+//! - TOML, opened and closed by `+++` lines.
+//! - The legacy format, YAML that is optionally opened by a `---` line and ends at the first blank
+//!   line. A `---`-opened block is only treated as legacy if it is never closed, or if the YAML
+//!   before its closing line is invalid, as happens when a legacy post contains a `---` rule.
+
+use serde::de::DeserializeOwned;
+use serde_yaml;
+use toml;
+
+use crate::errors::*;
+
+const YAML_FENCE: &str = "---";
+const YAML_END: &str = "...";
+const TOML_FENCE: &str = "+++";
+
+/// Splits a post into its deserialized front matter and the remaining content.
+pub fn parse<T>(post: &str) -> Result<(T, &str)>
+where
+    T: DeserializeOwned,
+{
+    let first_line = post.lines().next().map(str::trim_end);
+
+    match first_line {
+        Some(TOML_FENCE) => {
+            let (front_matter, content) = fenced(post, &[TOML_FENCE]).ok_or_else(|| {
+                Error::from(format!(
+                    "TOML front matter opened on line 1 is never closed by `{}`",
+                    TOML_FENCE
+                ))
+            })?;
+            let metadata =
+                toml::from_str(&front_matter).chain_err(|| "invalid TOML front matter")?;
+            Ok((metadata, content))
+        }
+        Some(YAML_FENCE) => match fenced(post, &[YAML_FENCE, YAML_END]) {
+            Some((front_matter, content)) => match serde_yaml::from_str(&front_matter) {
+                Ok(metadata) => Ok((metadata, content)),
+                Err(e) => {
+                    legacy(post).or_else(|_| Err(e).chain_err(|| "invalid YAML front matter"))
+                }
+            },
+            None => legacy(post),
+        },
+        _ => legacy(post),
+    }
+}
+
+/// Parses front matter that ends at the first blank line.
+fn legacy<T>(post: &str) -> Result<(T, &str)>
+where
+    T: DeserializeOwned,
+{
+    let mut parts = post.splitn(2, "\n\n");
+    let front_matter = parts.next().unwrap_or_default();
+    let content = parts.next().unwrap_or_default();
+    let metadata = serde_yaml::from_str(front_matter).chain_err(|| "invalid YAML front matter")?;
+    Ok((metadata, content))
+}
+
+/// Extracts the front matter between the fence on the first line and the first line matching one
+/// of `closing_fences`, returning it along with the content after the closing fence.
+///
+/// The opening fence is replaced by an empty line in the returned front matter, so that any errors
+/// reported by the deserializer have line numbers that match the original file.
+fn fenced<'a>(post: &'a str, closing_fences: &[&str]) -> Option<(String, &'a str)> {
+    let mut offset = 0;
+
+    for (index, line) in post.split('\n').enumerate() {
+        let end = offset + line.len() + 1;
+
+        if index > 0 && closing_fences.contains(&line.trim_end()) {
+            let front_matter = format!("\n{}", &post[post.find('\n')? + 1..offset]);
+            let content = post.get(end..).unwrap_or_default();
+            return Some((front_matter, content));
+        }
+
+        offset = end;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::parse;
+
+    type Metadata = BTreeMap<String, String>;
+
+    #[test]
+    fn yaml() {
+        let post = "---\ntitle: Hello\n\nauthor: Andy\n---\nContent\n\nMore content\n";
+        let (metadata, content) = parse::<Metadata>(post).unwrap();
+        assert_eq!(metadata["title"], "Hello");
+        assert_eq!(metadata["author"], "Andy");
+        assert_eq!(content, "Content\n\nMore content\n");
+    }
+
+    #[test]
+    fn yaml_document_end() {
+        let post = "---\ntitle: Hello\n...\nContent\n";
+        let (metadata, content) = parse::<Metadata>(post).unwrap();
+        assert_eq!(metadata["title"], "Hello");
+        assert_eq!(content, "Content\n");
+    }
+
+    #[test]
+    fn toml() {
+        let post = "+++\r\ntitle = \"Hello\"\r\n\r\nauthor = \"Andy\"\r\n+++\r\nContent\r\n";
+        let (metadata, content) = parse::<Metadata>(post).unwrap();
+        assert_eq!(metadata["title"], "Hello");
+        assert_eq!(metadata["author"], "Andy");
+        assert_eq!(content, "Content\r\n");
+    }
+
+    #[test]
+    fn legacy() {
+        let post = "---\ntitle: Hello\n\nContent\n\nMore content\n";
+        let (metadata, content) = parse::<Metadata>(post).unwrap();
+        assert_eq!(metadata["title"], "Hello");
+        assert_eq!(content, "Content\n\nMore content\n");
+
+        let post = "title: Hello\n\nContent\n";
+        let (metadata, content) = parse::<Metadata>(post).unwrap();
+        assert_eq!(metadata["title"], "Hello");
+        assert_eq!(content, "Content\n");
+    }
+
+    #[test]
+    fn legacy_with_rule() {
+        let post = "---\ntitle: Hello\n\nContent\n\n---\n\nMore content\n";
+        let (metadata, content) = parse::<Metadata>(post).unwrap();
+        assert_eq!(metadata["title"], "Hello");
+        assert_eq!(content, "Content\n\n---\n\nMore content\n");
+    }
+
+    #[test]
+    fn unterminated_toml() {
+        let post = "+++\ntitle = \"Hello\"\n\nContent\n";
+        let error = parse::<Metadata>(post).unwrap_err();
+        assert!(error.to_string().contains("line 1"));
+    }
+
+    #[test]
+    fn error_line_numbers() {
+        let post = "+++\ntitle = \"Hello\"\nauthor\n+++\nContent\n";
+        let error = parse::<Metadata>(post).unwrap_err();
+        let cause = error.iter().nth(1).unwrap().to_string();
+        assert!(cause.contains("line 3"), "{}", cause);
+
+        let post = "---\ntitle: Hello\nauthor: [Andy\n---\nContent\n";
+        let error = parse::<Metadata>(post).unwrap_err();
+        let cause = error.iter().nth(1).unwrap().to_string();
+        assert!(cause.contains("line 4"), "{}", cause);
+    }
+}
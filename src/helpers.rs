@@ -1,8 +1,12 @@
 //! Helpers for handlebars templates.
 
-use crate::handlebars::{Handlebars, Helper, RenderContext, RenderError};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use serde_json::Value;
 
+use crate::blog::human_readable_format;
+use crate::handlebars::{Handlebars, Helper, HelperDef, RenderContext, RenderError};
+
 const DEFAULT_SEPARATOR: &'static str = ", ";
 
 /// Simple helper to join an array.
@@ -55,8 +59,49 @@ pub fn join(h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> Resul
     Ok(())
 }
 
+/// Helper that formats an RFC 3339 timestamp as a human-readable date.
+///
+/// Dates are displayed in the time zone that the helper was created with.
+///
+/// # Parameters
+/// - date: The timestamp to format.
+pub struct FormatDate {
+    timezone: Tz,
+}
+
+impl FormatDate {
+    /// Creates a new helper that displays dates in the given time zone.
+    pub fn new(timezone: Tz) -> Self {
+        FormatDate { timezone }
+    }
+}
+
+impl HelperDef for FormatDate {
+    fn call(
+        &self,
+        h: &Helper<'_>,
+        _: &Handlebars,
+        rc: &mut RenderContext<'_>,
+    ) -> Result<(), RenderError> {
+        let date = h
+            .param(0)
+            .and_then(|p| p.value().as_str())
+            .ok_or_else(|| RenderError::new("Parameter for `format_date` must be a string."))?;
+
+        let date = DateTime::parse_from_rfc3339(date)
+            .map_err(|_| RenderError::new(format!("Invalid date for `format_date`: {}", date)))?
+            .with_timezone(&Utc);
+
+        let formatted = human_readable_format::format(&date, self.timezone);
+        rc.writer.write_all(formatted.as_bytes())?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use chrono_tz::America::Los_Angeles;
+
     use crate::handlebars::Handlebars;
 
     #[test]
@@ -73,4 +118,16 @@ mod tests {
         );
         assert_eq!(result.unwrap(), "one, two, three");
     }
+
+    #[test]
+    fn format_date() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("format_date", Box::new(super::FormatDate::new(Los_Angeles)));
+        handlebars
+            .register_template_string("template", "{{format_date this}}")
+            .unwrap();
+
+        let result = handlebars.render("template", &"2014-05-10T03:39:00+00:00");
+        assert_eq!(result.unwrap(), "May  9, 2014");
+    }
 }
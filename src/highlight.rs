@@ -0,0 +1,86 @@
+//! Syntax highlighting for code blocks.
+//!
+//! Code is highlighted when posts are rendered, producing spans with CSS classes instead of inline
+//! styles. The colors are provided by a stylesheet generated from the configured theme, so the
+//! theme can be changed without rendering the posts again.
+
+use lazy_static::lazy_static;
+use syntect::highlighting::ThemeSet;
+use syntect::html::{self, ClassStyle, ClassedHTMLGenerator};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+use crate::errors::*;
+
+/// The theme that is used if none is configured.
+pub const DEFAULT_THEME: &str = "base16-ocean.dark";
+
+/// Prefixed to every class in highlighted code, to avoid clashing with the rest of the site's CSS.
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+/// Languages without a syntax of their own that are highlighted with a similar syntax instead.
+const LANGUAGE_ALIASES: &[(&str, &str)] = &[("fish", "sh")];
+
+lazy_static! {
+    static ref SYNTAX_SET: SyntaxSet = SyntaxSet::load_defaults_newlines();
+    static ref THEME_SET: ThemeSet = ThemeSet::load_defaults();
+}
+
+/// Highlights code written in a language, given as a name or file extension such as `python` or
+/// `py`.
+///
+/// Returns `None` if the language is not recognized.
+pub fn highlight(code: &str, language: &str) -> Option<String> {
+    let language = LANGUAGE_ALIASES
+        .iter()
+        .find(|&&(alias, _)| alias == language)
+        .map_or(language, |&(_, language)| language);
+    let syntax = SYNTAX_SET.find_syntax_by_token(language)?;
+
+    let mut generator =
+        ClassedHTMLGenerator::new_with_class_style(syntax, &SYNTAX_SET, CLASS_STYLE);
+    for line in LinesWithEndings::from(code) {
+        generator.parse_html_for_line_which_includes_newline(line);
+    }
+
+    Some(generator.finalize())
+}
+
+/// Returns a stylesheet that colors highlighted code according to a theme.
+pub fn stylesheet(theme_name: &str) -> Result<String> {
+    let theme = match THEME_SET.themes.get(theme_name) {
+        Some(theme) => theme,
+        None => {
+            let themes = THEME_SET.themes.keys().cloned().collect::<Vec<_>>();
+            bail!(
+                "unknown highlighting theme `{}`, expected one of: {}",
+                theme_name,
+                themes.join(", ")
+            );
+        }
+    };
+
+    Ok(html::css_for_theme_with_class_style(theme, CLASS_STYLE))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn highlight() {
+        let html = super::highlight("def foo():\n    return 1\n", "python").unwrap();
+        assert!(
+            html.contains(r#"<span class="hl-storage hl-type hl-function hl-python">def</span>"#)
+        );
+
+        assert!(super::highlight("set number\n", "fish").is_some());
+        assert!(super::highlight("???", "not-a-language").is_none());
+    }
+
+    #[test]
+    fn stylesheet() {
+        let css = super::stylesheet(super::DEFAULT_THEME).unwrap();
+        assert!(css.contains(".hl-comment"));
+
+        assert!(super::stylesheet("nonexistent").is_err());
+    }
+}
@@ -0,0 +1,102 @@
+//! Rewriting of the tags in sanitized HTML.
+//!
+//! These functions only understand HTML as the sanitizer serializes it, where every attribute
+//! value is quoted with `"`.
+
+/// Calls a function with the name and attributes of each start tag in HTML that has attributes,
+/// copying everything else to the output. The function is responsible for writing the tag itself.
+pub fn for_each_tag<F>(html: &str, output: &mut String, mut f: F)
+where
+    F: FnMut(&mut String, &str, &mut Vec<(String, String)>),
+{
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let name_end = rest[1..]
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .map_or(rest.len(), |end| end + 1);
+        let has_attributes = name_end > 1 && rest[name_end..].starts_with(char::is_whitespace);
+        if !has_attributes {
+            output.push('<');
+            rest = &rest[1..];
+            continue;
+        }
+
+        let end = match find_tag_end(rest) {
+            Some(end) => end,
+            None => break,
+        };
+
+        let name = rest[1..name_end].to_owned();
+        let mut attributes = parse_attributes(&rest[name_end..end]);
+        f(output, &name, &mut attributes);
+
+        rest = &rest[end + 1..];
+    }
+
+    output.push_str(rest);
+}
+
+/// Returns the index of the `>` that closes the tag at the start of the string.
+fn find_tag_end(tag: &str) -> Option<usize> {
+    let mut in_quotes = false;
+    tag.char_indices()
+        .find(|&(_, c)| {
+            if c == '"' {
+                in_quotes = !in_quotes;
+            }
+            c == '>' && !in_quotes
+        })
+        .map(|(i, _)| i)
+}
+
+/// Parses attributes of the form `name="value"`, as serialized by the sanitizer. Values are left
+/// escaped.
+fn parse_attributes(attributes: &str) -> Vec<(String, String)> {
+    let mut parsed = vec![];
+    let mut rest = attributes.trim();
+
+    while !rest.is_empty() {
+        let name_end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or_else(|| rest.len());
+        let name = rest[..name_end].to_owned();
+        rest = &rest[name_end..];
+
+        let value = if rest.starts_with("=\"") {
+            let value_end = rest[2..].find('"').map_or(rest.len(), |end| end + 2);
+            let value = rest[2..value_end].to_owned();
+            rest = rest.get(value_end + 1..).unwrap_or_default();
+            value
+        } else {
+            String::new()
+        };
+
+        parsed.push((name, value));
+        rest = rest.trim_start();
+    }
+
+    parsed
+}
+
+/// Writes a start tag with the given attributes, which must already be escaped.
+pub fn write_tag(output: &mut String, name: &str, attributes: &[(String, String)]) {
+    output.push('<');
+    output.push_str(name);
+    for (name, value) in attributes {
+        output.push(' ');
+        output.push_str(name);
+        output.push_str("=\"");
+        output.push_str(&value.replace('"', "&quot;"));
+        output.push('"');
+    }
+    output.push('>');
+}
+
+/// Decodes the entities that the sanitizer uses in attribute values.
+pub fn unescape(value: &str) -> String {
+    value.replace("&quot;", "\"").replace("&amp;", "&")
+}
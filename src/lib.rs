@@ -16,19 +16,32 @@ use iron;
 pub mod blog;
 pub mod config;
 pub mod errors;
+pub mod feed;
 pub mod helpers;
+pub mod highlight;
+pub mod links;
 pub mod markdown;
+pub mod permalink;
 pub mod persistence;
 pub mod projects;
+pub mod redirects;
 pub mod routes;
+pub mod sanitize;
+pub mod shortcodes;
+pub mod watch;
 
+mod front_matter;
+mod html;
 mod models;
 mod schema;
+mod slug;
+mod wiki_links;
 
 use std::env;
 use std::fs::File;
 use std::io::prelude::*;
 use std::net::ToSocketAddrs;
+use std::sync::Arc;
 
 use diesel::connection::SimpleConnection;
 use handlebars_iron::handlebars;
@@ -36,16 +49,42 @@ use iron::prelude::*;
 use iron::Listening;
 use log::*;
 
+use crate::blog::Taxonomy;
 use crate::errors::*;
 
+/// Renders every blog post with each markdown backend, returning the posts whose HTML differs
+/// between hoedown and CommonMark.
+pub fn compare_renderers() -> Result<Vec<blog::RenderingDifference>> {
+    let config = load_config()?;
+    let projects =
+        projects::load("projects.yaml", &config).chain_err(|| "problem parsing projects")?;
+    let shortcodes = shortcodes::Shortcodes::load("templates/", &projects)
+        .chain_err(|| "problem loading shortcodes")?;
+    blog::compare_renderers(
+        "blog/",
+        &config,
+        &shortcodes,
+        markdown::Backend::Hoedown,
+        markdown::Backend::CommonMark,
+    )
+    .chain_err(|| "problem parsing blog posts")
+}
+
+/// Loads the configuration from the path in `WEBSITE_CONFIG`, or `config.yaml` by default.
+fn load_config() -> Result<config::Config> {
+    let config_path = env::var("WEBSITE_CONFIG").unwrap_or_else(|_| String::from("config.yaml"));
+    config::load(config_path).chain_err(|| "could not parse configuration")
+}
+
 /// Starts the server listening on the provided socket address.
 pub fn listen<A>(addr: A, database_uri: &str) -> Result<Listening>
 where
     A: ToSocketAddrs,
 {
-    let config_path = env::var("WEBSITE_CONFIG").unwrap_or_else(|_| String::from("config.yaml"));
-    let config = config::load(config_path).chain_err(|| "could not parse configuration")?;
-    let projects = projects::load("projects.yaml").chain_err(|| "problem parsing projects")?;
+    let config = load_config()?;
+    let projects =
+        projects::load("projects.yaml", &config).chain_err(|| "problem parsing projects")?;
+    let redirects = redirects::load("redirects.yaml").chain_err(|| "problem parsing redirects")?;
 
     // Insert blog posts into the database.
     let pool = persistence::get_connection_pool(database_uri)?;
@@ -59,9 +98,33 @@ where
     };
     connection.batch_execute(&schema).unwrap();
 
-    blog::load("blog/", &connection).chain_err(|| "problem parsing blog posts")?;
-
-    let handler = routes::handler(config, projects, pool)?;
+    let shortcodes = shortcodes::Shortcodes::load("templates/", &projects)
+        .chain_err(|| "problem loading shortcodes")?;
+    blog::load("blog/", &connection, &config, &shortcodes)
+        .chain_err(|| "problem parsing blog posts")?;
+
+    if config.links.check_internal {
+        let tags = blog::get_term_counts(&connection, Taxonomy::Tag)?;
+        let categories = blog::get_term_counts(&connection, Taxonomy::Category)?;
+        let is_term = |taxonomy, name: &str| {
+            let terms = match taxonomy {
+                Taxonomy::Tag => &tags,
+                Taxonomy::Category => &categories,
+            };
+            terms.iter().any(|term| term.name == name)
+        };
+
+        let broken_links = blog::broken_links(&connection, &config.site_url, |path| {
+            routes::is_page(path, is_term) || redirects.lookup(path).is_some()
+        })?;
+        for link in broken_links {
+            warn!("{} links to nonexistent page {}", link.file_name, link.path);
+        }
+    }
+    watch::watch_posts("blog/", pool.clone(), config.clone(), Arc::new(shortcodes))
+        .chain_err(|| "could not watch blog posts")?;
+
+    let handler = routes::handler(config, projects, redirects, pool)?;
 
     info!("initialization complete");
 
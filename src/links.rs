@@ -0,0 +1,233 @@
+//! Decoration and checking of links in rendered markdown.
+//!
+//! Links to other websites are marked up according to a [`LinkPolicy`]: they are given a `rel`
+//! attribute so that the linked page cannot control this one, and optionally a class and a
+//! target. Links to this website are left alone, but may be checked against the pages that exist.
+//!
+//! A link is external if it is an absolute URL whose host is not the host of the site URL.
+//!
+//! HTML that is read somewhere other than the website, such as in a feed, may have its links and
+//! image sources made absolute with [`absolutize`].
+//!
+//! [`absolutize`]: fn.absolutize.html
+//! [`LinkPolicy`]: struct.LinkPolicy.html
+
+use serde::Deserialize;
+use url::Url;
+
+use crate::html::{for_each_tag, unescape, write_tag};
+use crate::markdown::Html;
+
+/// The rules for decorating links in rendered markdown.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct LinkPolicy {
+    /// Whether external links are marked `nofollow`, so that search engines do not follow them.
+    pub nofollow: bool,
+
+    /// A class added to external links, so that they may be styled differently. Templates mark
+    /// the external links they contain with the same class by hand.
+    pub external_class: Option<String>,
+
+    /// The browsing context that external links open in, such as `_blank`.
+    pub target: Option<String>,
+
+    /// Whether internal links are checked against the pages of the website when the blog is
+    /// loaded. Broken links are logged.
+    pub check_internal: bool,
+}
+
+impl Default for LinkPolicy {
+    fn default() -> Self {
+        LinkPolicy {
+            nofollow: false,
+            external_class: Some(String::from("external")),
+            target: None,
+            check_internal: true,
+        }
+    }
+}
+
+/// A link in HTML that points somewhere else on the website.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InternalLink {
+    /// The path of the page that the link points at, without its query or fragment.
+    pub path: String,
+}
+
+/// Rewrites the external links in HTML according to a policy.
+///
+/// The HTML is expected to have been sanitized first, so that every attribute is quoted.
+pub fn decorate(html: &str, policy: &LinkPolicy, site_url: &Url) -> Html {
+    let mut output = String::with_capacity(html.len());
+
+    for_each_link(html, &mut output, |output, attributes| {
+        let is_external = attributes
+            .iter()
+            .find(|(name, _)| name == "href")
+            .map_or(false, |(_, href)| is_external(href, site_url));
+
+        if is_external {
+            let mut rel = vec!["noopener"];
+            if policy.nofollow {
+                rel.push("nofollow");
+            }
+            add_tokens(attributes, "rel", &rel);
+
+            if let Some(ref class) = policy.external_class {
+                add_tokens(attributes, "class", &[class]);
+            }
+
+            if let Some(ref target) = policy.target {
+                attributes.retain(|(name, _)| name != "target");
+                attributes.push((String::from("target"), target.to_owned()));
+            }
+        }
+
+        write_tag(output, "a", attributes);
+    });
+
+    Html::new(output)
+}
+
+/// Resolves the `href` and `src` attributes of every tag in HTML against a base URL.
+///
+/// The base URL should be the URL of the page that the HTML was written for, so that fragment
+/// links still point at that page. Attributes that cannot be resolved are left alone.
+pub fn absolutize(html: &str, base: &Url) -> Html {
+    let mut output = String::with_capacity(html.len());
+
+    for_each_tag(html, &mut output, |output, name, attributes| {
+        for (attribute, value) in attributes.iter_mut() {
+            if attribute != "href" && attribute != "src" {
+                continue;
+            }
+
+            if let Ok(url) = base.join(&unescape(value)) {
+                *value = url.as_str().replace('&', "&amp;");
+            }
+        }
+
+        write_tag(output, name, attributes);
+    });
+
+    Html::new(output)
+}
+
+/// Returns the links in HTML that point at other pages of the website.
+///
+/// Links to fragments of the same page are not included.
+pub fn internal_links(html: &str, site_url: &Url) -> Vec<InternalLink> {
+    let mut links = vec![];
+
+    for_each_link(html, &mut String::new(), |_, attributes| {
+        let href = match attributes.iter().find(|(name, _)| name == "href") {
+            Some((_, href)) if !href.starts_with('#') => unescape(href),
+            _ => return,
+        };
+
+        match site_url.join(&href) {
+            Ok(ref url) if is_same_host(url, site_url) => {
+                links.push(InternalLink {
+                    path: url.path().to_owned(),
+                });
+            }
+            _ => (),
+        }
+    });
+
+    links
+}
+
+fn is_external(href: &str, site_url: &Url) -> bool {
+    let href = unescape(href);
+    if !(href.starts_with("http://") || href.starts_with("https://") || href.starts_with("//")) {
+        return false;
+    }
+
+    match site_url.join(&href) {
+        Ok(url) => !is_same_host(&url, site_url),
+        Err(_) => false,
+    }
+}
+
+fn is_same_host(url: &Url, site_url: &Url) -> bool {
+    url.host_str().is_some() && url.host_str() == site_url.host_str()
+}
+
+/// Calls a function with the attributes of each `<a>` tag in HTML, copying everything else to
+/// the output. The function is responsible for writing the tag itself.
+fn for_each_link<F>(html: &str, output: &mut String, mut f: F)
+where
+    F: FnMut(&mut String, &mut Vec<(String, String)>),
+{
+    for_each_tag(html, output, |output, name, attributes| {
+        if name == "a" {
+            f(output, attributes);
+        } else {
+            write_tag(output, name, attributes);
+        }
+    });
+}
+
+/// Adds whitespace-separated tokens to an attribute, unless it already contains them.
+fn add_tokens(attributes: &mut Vec<(String, String)>, name: &str, tokens: &[&str]) {
+    let index = match attributes.iter().position(|(n, _)| n == name) {
+        Some(index) => index,
+        None => {
+            attributes.push((name.to_owned(), String::new()));
+            attributes.len() - 1
+        }
+    };
+
+    let value = &mut attributes[index].1;
+    for token in tokens {
+        if !value.split_whitespace().any(|existing| existing == *token) {
+            if !value.is_empty() {
+                value.push(' ');
+            }
+            value.push_str(token);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use super::{InternalLink, LinkPolicy};
+
+    #[test]
+    fn decorate() {
+        let site_url = Url::parse("https://acrussell.com").unwrap();
+        let html = r#"<p><a href="https://www.vim.org" rel="footnote">Vim</a>, <a href="/about">me</a>, <a href="https://acrussell.com/blog">blog</a> and <a href="//github.com/euclio" class="big">GitHub</a></p>"#;
+
+        assert_eq!(
+            &*super::decorate(html, &LinkPolicy::default(), &site_url),
+            r#"<p><a href="https://www.vim.org" rel="footnote noopener" class="external">Vim</a>, <a href="/about">me</a>, <a href="https://acrussell.com/blog">blog</a> and <a href="//github.com/euclio" class="big external" rel="noopener">GitHub</a></p>"#
+        );
+
+        let policy = LinkPolicy {
+            nofollow: true,
+            external_class: None,
+            target: Some(String::from("_blank")),
+            ..Default::default()
+        };
+        assert_eq!(
+            &*super::decorate(r#"<a href="http://vim.org">Vim</a>"#, &policy, &site_url),
+            r#"<a href="http://vim.org" rel="noopener nofollow" target="_blank">Vim</a>"#
+        );
+    }
+
+    #[test]
+    fn internal_links() {
+        let site_url = Url::parse("https://acrussell.com").unwrap();
+        let html = r##"<a href="/about?x=1#me">me</a> <a href="#section">here</a> <a href="https://acrussell.com/blog">blog</a> <a href="https://vim.org/about">Vim</a> <a href="//github.com/euclio">GitHub</a> <a href="//acrussell.com/projects">projects</a> <a href="mailto:me@acrussell.com">mail</a>"##;
+
+        let paths = super::internal_links(html, &site_url)
+            .into_iter()
+            .map(|InternalLink { path }| path)
+            .collect::<Vec<_>>();
+        assert_eq!(paths, &["/about", "/blog", "/projects"]);
+    }
+}
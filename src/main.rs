@@ -55,8 +55,21 @@ fn main() {
             ",
                 ),
         )
+        .arg(
+            Arg::with_name("compare_renderers")
+                .long("compare-renderers")
+                .help(
+                    "Instead of starting the server, render every blog post with both markdown \
+                     backends and print the lines of HTML that differ.",
+                ),
+        )
         .get_matches();
 
+    if matches.is_present("compare_renderers") {
+        exit_on_error(compare_renderers());
+        return;
+    }
+
     let port = matches
         .value_of("port")
         .and_then(|port| port.parse::<u16>().ok())
@@ -65,7 +78,11 @@ fn main() {
         .value_of("db_uri")
         .unwrap_or_else(|| DEFAULT_DATABASE_URI);
 
-    if let Err(ref e) = run(port, db_uri) {
+    exit_on_error(run(port, db_uri));
+}
+
+fn exit_on_error(result: Result<()>) {
+    if let Err(ref e) = result {
         let stderr = &mut io::stderr();
         let errmsg = "error writing to stderr";
 
@@ -83,6 +100,20 @@ fn main() {
     }
 }
 
+fn compare_renderers() -> Result<()> {
+    let differences = website::compare_renderers()?;
+
+    for difference in &differences {
+        println!("{}:\n{}", difference.file_name, difference.diff);
+    }
+    println!(
+        "{} posts render differently with hoedown and commonmark",
+        differences.len()
+    );
+
+    Ok(())
+}
+
 fn run(port: u16, db_uri: &str) -> Result<()> {
     let _ = website::listen(("localhost", port), db_uri).chain_err(|| "could not start server")?;
     Ok(())
@@ -1,12 +1,125 @@
 //! Utilities for rendering Markdown.
+//!
+//! The syntax that is recognized is controlled by a set of [`Extension`]s, which are configured for
+//! the whole site and may be overridden by individual posts.
+//!
+//! Markdown may be rendered by more than one [`Backend`]. hoedown is used by default, and a
+//! CommonMark-compliant renderer is available as an alternative.
+//!
+//! [`Extension`]: enum.Extension.html
+//! [`Backend`]: enum.Backend.html
 
+mod commonmark;
+mod hoedown;
+
+use std::collections::HashSet;
+use std::fmt;
 use std::ops::Deref;
 
-use hoedown::renderer::html;
-use hoedown::{self, Render};
-use hoedown::{AUTOLINK, FENCED_CODE, TABLES};
+use gh_emoji;
+use lazy_static::lazy_static;
+use serde::{self, Deserialize, Serialize};
+
+use crate::highlight;
+use crate::slug;
+
+pub use self::commonmark::CommonMarkRenderer;
+pub use self::hoedown::HoedownRenderer;
+
+/// The extensions that are enabled if none are configured.
+pub const DEFAULT_EXTENSIONS: &[Extension] = &[
+    Extension::Autolink,
+    Extension::FencedCode,
+    Extension::Tables,
+];
+
+lazy_static! {
+    static ref EMOJI: gh_emoji::Replacer = gh_emoji::Replacer::new();
+}
+
+/// An optional feature of the markdown syntax.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Extension {
+    /// Turns bare URLs and email addresses into links.
+    Autolink,
+
+    /// Code blocks delimited by three backticks, with an optional language.
+    FencedCode,
+
+    /// Tables delimited by pipes.
+    Tables,
 
-use serde;
+    /// Footnote references such as `[^1]`, with the footnotes collected at the end of the document.
+    Footnotes,
+
+    /// Struck out text, such as `~~wrong~~`.
+    Strikethrough,
+
+    /// Superscript text, such as `2^(10)`.
+    Superscript,
+
+    /// Highlighted text, such as `==important==`.
+    Highlight,
+
+    /// Curly quotes, dashes and ellipses in place of their ASCII equivalents.
+    Smartypants,
+
+    /// Emoji in place of shortcodes such as `:tada:`.
+    Emoji,
+}
+
+/// The library used to render markdown.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// Renders with [`HoedownRenderer`](struct.HoedownRenderer.html).
+    Hoedown,
+
+    /// Renders with [`CommonMarkRenderer`](struct.CommonMarkRenderer.html).
+    CommonMark,
+}
+
+impl Backend {
+    /// Every backend, for comparing their output.
+    pub const ALL: &'static [Backend] = &[Backend::Hoedown, Backend::CommonMark];
+
+    /// Returns the renderer for the backend.
+    pub fn renderer(self) -> &'static dyn Renderer {
+        match self {
+            Backend::Hoedown => &HoedownRenderer,
+            Backend::CommonMark => &CommonMarkRenderer,
+        }
+    }
+}
+
+impl Default for Backend {
+    fn default() -> Self {
+        Backend::Hoedown
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Backend::Hoedown => f.write_str("hoedown"),
+            Backend::CommonMark => f.write_str("commonmark"),
+        }
+    }
+}
+
+/// A markdown parser and HTML renderer.
+///
+/// Renderers give each heading an ID derived from its text and a link to itself, and syntax
+/// highlight fenced code blocks in a recognized language. Extensions that a renderer does not
+/// support are ignored.
+pub trait Renderer: Sync {
+    /// Renders a markdown string into unescaped HTML, collecting its headings.
+    ///
+    /// If `raw_html` is false, HTML written in the markdown is escaped instead of being passed
+    /// through.
+    fn render(&self, markdown: &str, extensions: &[Extension], raw_html: bool) -> Document;
+}
 
 /// An owned string containing Markdown.
 #[derive(Debug, Clone)]
@@ -46,6 +159,60 @@ impl Html {
     pub fn new(inner: String) -> Html {
         Html(inner)
     }
+
+    /// Returns the text of the HTML with all tags removed.
+    ///
+    /// Tags are replaced with whitespace so that words in adjacent elements stay separate, and the
+    /// entities that the markdown renderer produces are decoded.
+    pub fn plain_text(&self) -> String {
+        let mut text = String::with_capacity(self.0.len());
+        let mut in_tag = false;
+
+        for c in self.0.chars() {
+            match c {
+                '<' => in_tag = true,
+                '>' if in_tag => {
+                    in_tag = false;
+                    text.push(' ');
+                }
+                _ if !in_tag => text.push(c),
+                _ => (),
+            }
+        }
+
+        text.replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&#39;", "'")
+            .replace("&#x27;", "'")
+            .replace("&amp;", "&")
+    }
+
+    /// Returns the HTML with its code blocks removed, leaving only the prose.
+    pub fn prose(&self) -> Html {
+        let mut prose = String::with_capacity(self.0.len());
+        let mut rest = &self.0[..];
+        while let Some(start) = rest.find("<pre") {
+            prose.push_str(&rest[..start]);
+            rest = rest[start..]
+                .find("</pre>")
+                .map_or("", |end| &rest[start + end + "</pre>".len()..]);
+        }
+        prose.push_str(rest);
+
+        Html(prose)
+    }
+
+    /// Returns the number of words in the text of the HTML.
+    ///
+    /// Punctuation and entities that stand on their own, such as dashes, are not counted.
+    pub fn word_count(&self) -> usize {
+        self.plain_text()
+            .split_whitespace()
+            .filter(|word| word.chars().any(char::is_alphanumeric))
+            .filter(|word| !(word.starts_with('&') && word.ends_with(';')))
+            .count()
+    }
 }
 
 impl Deref for Html {
@@ -65,10 +232,440 @@ impl serde::Serialize for Html {
     }
 }
 
-/// Renders a markdown string into unescaped HTML.
-pub fn render_html(markdown: &str) -> Html {
-    let markdown = hoedown::Markdown::new(markdown).extensions(AUTOLINK | FENCED_CODE | TABLES);
+/// A heading in a rendered markdown document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heading {
+    /// The level of the heading, from 1 to 6.
+    pub level: i32,
+
+    /// The ID of the heading, which may be used as a URL fragment.
+    pub id: String,
+
+    /// The text of the heading.
+    pub title: String,
+}
+
+/// An entry in a table of contents.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TocEntry {
+    /// The ID of the heading that the entry links to.
+    pub id: String,
+
+    /// The text of the heading.
+    pub title: String,
+
+    /// The entries for the subsections beneath the heading.
+    pub children: Vec<TocEntry>,
+}
+
+/// Markdown that has been rendered into HTML.
+#[derive(Debug)]
+pub struct Document {
+    /// The rendered HTML.
+    pub html: Html,
+
+    /// The headings in the document, in the order that they appear.
+    pub headings: Vec<Heading>,
+}
+
+/// Tracks the IDs given to the headings of a document, so that each is unique.
+#[derive(Debug, Default)]
+struct HeadingIds(HashSet<String>);
+
+impl HeadingIds {
+    /// Returns an ID based on the heading's title that has not been used in the document yet.
+    fn unique_id(&mut self, title: &str) -> String {
+        let base = match slug::slugify(title) {
+            ref slug if slug.is_empty() => String::from("section"),
+            slug => slug,
+        };
+
+        let mut id = base.clone();
+        let mut suffix = 1;
+        while self.0.contains(&id) {
+            id = format!("{}-{}", base, suffix);
+            suffix += 1;
+        }
+
+        self.0.insert(id.clone());
+        id
+    }
+}
+
+/// Returns the text of a heading's rendered content, with whitespace collapsed.
+fn heading_title(content: &str) -> String {
+    Html::new(content.to_owned())
+        .plain_text()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns the HTML for a heading with an ID and a link to itself.
+fn heading_html(level: i32, id: &str, content: &str) -> String {
+    format!(
+        r##"<h{level} id="{id}">{content}<a class="anchor" href="#{id}" aria-label="Link to this section"><span class="fa fa-link"></span></a></h{level}>"##,
+        level = level,
+        id = id,
+        content = content
+    )
+}
+
+/// Returns the HTML for a syntax highlighted code block, if the language is recognized.
+fn code_block_html(code: &str, language: &str) -> Option<String> {
+    if !language
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "+-#.".contains(c))
+    {
+        return None;
+    }
+
+    highlight::highlight(code, language).map(|highlighted| {
+        format!(
+            r#"<pre class="highlight"><code class="language-{}">{}</code></pre>"#,
+            language, highlighted
+        )
+    })
+}
 
-    let mut html = hoedown::Html::new(html::Flags::empty(), 0);
-    Html(html.render(&markdown).to_str().unwrap().to_owned())
+/// Renders a markdown string into unescaped HTML with the given backend.
+pub fn render_html(
+    markdown: &str,
+    backend: Backend,
+    extensions: &[Extension],
+    raw_html: bool,
+) -> Html {
+    render(markdown, backend, extensions, raw_html).html
+}
+
+/// Renders a markdown string into unescaped HTML with the given backend, collecting its headings.
+///
+/// If `raw_html` is false, HTML written in the markdown is escaped instead of being passed through.
+pub fn render(
+    markdown: &str,
+    backend: Backend,
+    extensions: &[Extension],
+    raw_html: bool,
+) -> Document {
+    let mut document = backend.renderer().render(markdown, extensions, raw_html);
+
+    if extensions.contains(&Extension::Emoji) {
+        document.html = Html(replace_emoji(&document.html));
+    }
+
+    document
+}
+
+/// Rewrites the text of a markdown document that is not code, copying fenced code blocks, indented
+/// code blocks and code spans to the output unchanged.
+///
+/// `rewrite` is called with each run of text between code, which may span several lines, and
+/// appends the rewritten text to the output. A run of backticks that is never closed is text.
+///
+/// Any indented line that follows a blank line is taken to start a code block, so a paragraph
+/// that continues a list item after a blank line is not rewritten either.
+pub fn rewrite_text<F, E>(markdown: &str, mut rewrite: F) -> Result<String, E>
+where
+    F: FnMut(&str, &mut String) -> Result<(), E>,
+{
+    let mut output = String::with_capacity(markdown.len());
+    let mut fence: Option<(char, usize)> = None;
+    let mut in_indented_block = false;
+    let mut follows_blank = true;
+    let mut text_start = 0;
+    let mut offset = 0;
+
+    for line in markdown.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let marker = trimmed.chars().next().filter(|&c| c == '`' || c == '~');
+        let marker_len = marker.map_or(0, |c| trimmed.chars().take_while(|&m| m == c).count());
+
+        let is_blank = trimmed.is_empty();
+        in_indented_block = fence.is_none()
+            && if is_blank {
+                in_indented_block
+            } else {
+                is_indented(line) && (follows_blank || in_indented_block)
+            };
+        follows_blank = is_blank;
+
+        let is_code = in_indented_block
+            || match (fence, marker) {
+                (Some((fence_char, fence_len)), Some(c))
+                    if c == fence_char && marker_len >= fence_len =>
+                {
+                    fence = None;
+                    true
+                }
+                (Some(_), _) => true,
+                (None, Some(c)) if marker_len >= 3 => {
+                    fence = Some((c, marker_len));
+                    true
+                }
+                (None, _) => false,
+            };
+
+        if is_code {
+            rewrite_outside_spans(&markdown[text_start..offset], &mut rewrite, &mut output)?;
+            output.push_str(line);
+            text_start = offset + line.len();
+        }
+
+        offset += line.len();
+    }
+
+    rewrite_outside_spans(&markdown[text_start..], &mut rewrite, &mut output)?;
+    Ok(output)
+}
+
+/// Rewrites the text around the code spans in some markdown, copying the code spans unchanged.
+fn rewrite_outside_spans<F, E>(text: &str, rewrite: &mut F, output: &mut String) -> Result<(), E>
+where
+    F: FnMut(&str, &mut String) -> Result<(), E>,
+{
+    let mut text_start = 0;
+    let mut search_start = 0;
+
+    while let Some(start) = text[search_start..].find('`').map(|i| search_start + i) {
+        // A code span is closed by a run of backticks of the same length.
+        let ticks = text[start..].chars().take_while(|&c| c == '`').count();
+        let delimiter = &text[start..start + ticks];
+
+        match text[start + ticks..].find(delimiter) {
+            Some(end) => {
+                let end = start + ticks + end + ticks;
+                rewrite(&text[text_start..start], output)?;
+                output.push_str(&text[start..end]);
+                text_start = end;
+                search_start = end;
+            }
+            None => search_start = start + ticks,
+        }
+    }
+
+    rewrite(&text[text_start..], output)
+}
+
+/// Returns whether a line is indented by at least four columns, as the lines of an indented code
+/// block are.
+fn is_indented(line: &str) -> bool {
+    let mut width = 0;
+    for c in line.chars() {
+        match c {
+            ' ' => width += 1,
+            '\t' => width += 4 - width % 4,
+            _ => return false,
+        }
+
+        if width >= 4 {
+            return !line.trim().is_empty();
+        }
+    }
+
+    false
+}
+
+/// Replaces emoji shortcodes in the text of the HTML, leaving tags and code untouched.
+fn replace_emoji(html: &str) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut code_depth = 0usize;
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        let (text, tail) = rest.split_at(rest.find('<').unwrap_or(rest.len()));
+        if code_depth == 0 {
+            output.push_str(&EMOJI.replace_all(text));
+        } else {
+            output.push_str(text);
+        }
+
+        let (tag, tail) = tail.split_at(tail.find('>').map_or(tail.len(), |end| end + 1));
+        let name = tag
+            .trim_start_matches('<')
+            .split(|c: char| c.is_whitespace() || c == '>')
+            .next()
+            .unwrap_or_default();
+        match name {
+            "code" | "pre" => code_depth += 1,
+            "/code" | "/pre" => code_depth = code_depth.saturating_sub(1),
+            _ => (),
+        }
+        output.push_str(tag);
+
+        rest = tail;
+    }
+
+    output
+}
+
+/// Builds a table of contents from a list of headings.
+///
+/// Each heading contains the headings of a greater level that follow it, up until the next heading
+/// of the same or lesser level.
+pub fn table_of_contents(headings: &[Heading]) -> Vec<TocEntry> {
+    let mut entries = vec![];
+    let mut rest = headings;
+
+    while let Some((heading, tail)) = rest.split_first() {
+        let end = tail
+            .iter()
+            .position(|subheading| subheading.level <= heading.level)
+            .unwrap_or_else(|| tail.len());
+
+        entries.push(TocEntry {
+            id: heading.id.clone(),
+            title: heading.title.clone(),
+            children: table_of_contents(&tail[..end]),
+        });
+
+        rest = &tail[end..];
+    }
+
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Backend, Extension, DEFAULT_EXTENSIONS};
+
+    #[test]
+    fn word_count() {
+        let html = super::render_html(
+            "# Patching Vim\n\nI use [Vim](http://www.vim.org) *almost* every day &mdash; \
+             it's great.\n\n```\n:set number\n```\n",
+            Backend::Hoedown,
+            DEFAULT_EXTENSIONS,
+            true,
+        );
+
+        assert!(html.plain_text().contains("it's great"));
+        assert_eq!(html.word_count(), 12);
+    }
+
+    #[test]
+    fn heading_anchors() {
+        for &backend in Backend::ALL {
+            let document = super::render(
+                "# Setup\n\n## Installing `vim`\n\n# Setup\n\n# ???\n",
+                backend,
+                DEFAULT_EXTENSIONS,
+                true,
+            );
+
+            let ids = document
+                .headings
+                .iter()
+                .map(|heading| &*heading.id)
+                .collect::<Vec<_>>();
+            assert_eq!(ids, &["setup", "installing-vim", "setup-1", "section"]);
+            assert_eq!(document.headings[1].title, "Installing vim");
+
+            assert!(document.html.contains(
+                r##"<h2 id="installing-vim">Installing <code>vim</code><a class="anchor" href="#installing-vim""##
+            ));
+        }
+    }
+
+    #[test]
+    fn highlighted_code() {
+        for &backend in Backend::ALL {
+            let html = super::render_html(
+                "```python\nprint('<hi>')\n```\n\n```\nplain\n```\n",
+                backend,
+                DEFAULT_EXTENSIONS,
+                true,
+            );
+
+            assert!(html.contains(r#"<pre class="highlight"><code class="language-python">"#));
+            assert!(html.contains("&lt;hi&gt;"));
+            assert!(html.contains("<pre><code>plain\n</code></pre>"));
+        }
+    }
+
+    #[test]
+    fn table_of_contents() {
+        let document = super::render(
+            "## Intro\n\n### Details\n\n#### More\n\n## Outro\n\n# Top\n",
+            Backend::Hoedown,
+            DEFAULT_EXTENSIONS,
+            true,
+        );
+        let toc = super::table_of_contents(&document.headings);
+
+        let titles = toc.iter().map(|entry| &*entry.title).collect::<Vec<_>>();
+        assert_eq!(titles, &["Intro", "Outro", "Top"]);
+        assert_eq!(toc[0].children.len(), 1);
+        assert_eq!(toc[0].children[0].title, "Details");
+        assert_eq!(toc[0].children[0].children[0].title, "More");
+        assert!(toc[1].children.is_empty());
+    }
+
+    #[test]
+    fn extensions() {
+        let markdown = "A \"quote\"[^1] -- ~~not~~ ==this== :tada:\n\n\
+                        `:tada:`\n\n[^1]: A footnote.\n";
+
+        let plain = super::render_html(markdown, Backend::Hoedown, DEFAULT_EXTENSIONS, true);
+        assert!(plain.contains("[^1]"));
+        assert!(plain.contains("~~not~~"));
+        assert!(plain.contains(":tada:"));
+
+        let html = super::render_html(
+            markdown,
+            Backend::Hoedown,
+            &[
+                Extension::Footnotes,
+                Extension::Strikethrough,
+                Extension::Highlight,
+                Extension::Smartypants,
+                Extension::Emoji,
+            ],
+            true,
+        );
+        assert!(html.contains("&ldquo;quote&rdquo;"));
+        assert!(html.contains("&ndash;"));
+        assert!(html.contains(r##"<sup id="fnref1"><a href="#fn1""##));
+        assert!(html.contains("<del>not</del>"));
+        assert!(html.contains("<mark>this</mark>"));
+        assert!(html.contains("\u{1f389}</p>"));
+        assert!(html.contains("<code>:tada:</code>"));
+    }
+
+    #[test]
+    fn commonmark() {
+        let markdown = "A \"quote\"[^1] -- ~~not~~ :tada:\n\n\
+                        * [link](</my url>)\n\n[^1]: A footnote.\n";
+
+        let html = super::render_html(
+            markdown,
+            Backend::CommonMark,
+            &[
+                Extension::Footnotes,
+                Extension::Strikethrough,
+                Extension::Smartypants,
+                Extension::Emoji,
+            ],
+            true,
+        );
+        assert!(html.contains("\u{201c}quote\u{201d}"));
+        assert!(html.contains("<del>not</del>"));
+        assert!(html.contains(r##"<sup class="footnote-reference"><a href="#1">1</a></sup>"##));
+        assert!(html.contains(r#"<a href="/my%20url">link</a>"#));
+        assert!(html.contains("\u{1f389}</p>"));
+    }
+
+    #[test]
+    fn raw_html() {
+        for &backend in Backend::ALL {
+            let markdown = "<div>block</div>\n\nSome <b>inline</b> HTML.\n";
+
+            let html = super::render_html(markdown, backend, DEFAULT_EXTENSIONS, true);
+            assert!(html.contains("<div>block</div>"));
+            assert!(html.contains("<b>inline</b>"));
+
+            let html = super::render_html(markdown, backend, DEFAULT_EXTENSIONS, false);
+            assert!(html.contains("&lt;div&gt;block&lt;/div&gt;"));
+            assert!(html.contains("&lt;b&gt;inline&lt;/b&gt;"));
+        }
+    }
 }
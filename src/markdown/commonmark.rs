@@ -0,0 +1,107 @@
+//! A renderer backed by pulldown-cmark, a pure-Rust implementation of CommonMark.
+
+use pulldown_cmark::{html, CodeBlockKind, Event, Options, Parser, Tag};
+
+use super::{code_block_html, heading_html, heading_title, Document, Extension, HeadingIds};
+use super::{Heading, Html, Renderer};
+
+/// Renders markdown according to the CommonMark specification, with GitHub Flavored Markdown
+/// extensions.
+///
+/// Fenced code is part of CommonMark, so it is always enabled. The `autolink`, `superscript` and
+/// `highlight` extensions are not supported, and are ignored.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct CommonMarkRenderer;
+
+impl Renderer for CommonMarkRenderer {
+    fn render(&self, markdown: &str, extensions: &[Extension], raw_html: bool) -> Document {
+        let options = extensions
+            .iter()
+            .filter_map(|&extension| parser_option(extension))
+            .fold(Options::empty(), |options, option| options | option);
+
+        let mut events = vec![];
+        let mut headings = vec![];
+        let mut ids = HeadingIds::default();
+
+        // Headings and code blocks are rendered by hand, so their contents are buffered until the
+        // end of the element.
+        let mut heading_events: Option<Vec<Event<'_>>> = None;
+        let mut code: Option<(String, String)> = None;
+
+        for event in Parser::new_ext(markdown, options) {
+            let event = match event {
+                Event::Html(html) if !raw_html => Event::Text(html),
+                event => event,
+            };
+
+            match event {
+                Event::Start(Tag::Heading(..)) => heading_events = Some(vec![]),
+                Event::End(Tag::Heading(level, ..)) => {
+                    let mut content = String::new();
+                    html::push_html(&mut content, heading_events.take().unwrap().into_iter());
+
+                    let level = level as i32;
+                    let title = heading_title(&content);
+                    let id = ids.unique_id(&title);
+
+                    events.push(Event::Html(heading_html(level, &id, &content).into()));
+                    events.push(Event::Html("\n".into()));
+                    headings.push(Heading { level, id, title });
+                }
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref info))) => {
+                    let language = info.split_whitespace().next().unwrap_or_default();
+                    code = Some((language.to_owned(), String::new()));
+                    events.push(event.clone());
+                }
+                Event::Text(ref text) if code.is_some() => {
+                    code.as_mut().unwrap().1.push_str(text);
+                    events.push(event.clone());
+                }
+                Event::End(Tag::CodeBlock(_)) if code.is_some() => {
+                    let (language, code) = code.take().unwrap();
+                    match code_block_html(&code, &language) {
+                        Some(highlighted) => {
+                            // Replace the unhighlighted block that has been collected so far.
+                            while let Some(event) = events.pop() {
+                                if let Event::Start(Tag::CodeBlock(_)) = event {
+                                    break;
+                                }
+                            }
+                            events.push(Event::Html(highlighted.into()));
+                            events.push(Event::Html("\n".into()));
+                        }
+                        None => events.push(event),
+                    }
+                }
+                event => match heading_events {
+                    Some(ref mut heading_events) => heading_events.push(event),
+                    None => events.push(event),
+                },
+            }
+        }
+
+        let mut output = String::with_capacity(markdown.len() * 3 / 2);
+        html::push_html(&mut output, events.into_iter());
+
+        Document {
+            html: Html::new(output),
+            headings,
+        }
+    }
+}
+
+/// Returns the corresponding parser option, if the extension is supported.
+fn parser_option(extension: Extension) -> Option<Options> {
+    match extension {
+        Extension::Tables => Some(Options::ENABLE_TABLES),
+        Extension::Footnotes => Some(Options::ENABLE_FOOTNOTES),
+        Extension::Strikethrough => Some(Options::ENABLE_STRIKETHROUGH),
+        Extension::Smartypants => Some(Options::ENABLE_SMART_PUNCTUATION),
+        Extension::Autolink
+        | Extension::FencedCode
+        | Extension::Superscript
+        | Extension::Highlight
+        | Extension::Emoji => None,
+    }
+}
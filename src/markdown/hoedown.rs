@@ -0,0 +1,131 @@
+//! A renderer backed by hoedown, a C library implementing a superset of the original Markdown.
+
+use std::io::Write;
+
+use ::hoedown::renderer::html;
+use ::hoedown::{self, wrap, Buffer, Render, Wrapper};
+
+use super::{code_block_html, heading_html, heading_title, Document, Extension, HeadingIds};
+use super::{Heading, Html, Renderer};
+
+/// Renders markdown with hoedown.
+///
+/// This renderer supports every extension.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct HoedownRenderer;
+
+impl Renderer for HoedownRenderer {
+    fn render(&self, markdown: &str, extensions: &[Extension], raw_html: bool) -> Document {
+        let flags = extensions
+            .iter()
+            .filter_map(|&extension| hoedown_extension(extension))
+            .fold(hoedown::Extension::empty(), |flags, flag| flags | flag);
+        let markdown = hoedown::Markdown::new(markdown).extensions(flags);
+
+        let mut renderer = AnchoredHtml {
+            html: hoedown::Html::new(html::Flags::empty(), 0),
+            headings: vec![],
+            ids: HeadingIds::default(),
+            raw_html,
+        };
+        let mut output = renderer.render(&markdown);
+
+        if extensions.contains(&Extension::Smartypants) {
+            let mut smart_output = Buffer::new(output.len());
+            html::smartypants(&output, &mut smart_output);
+            output = smart_output;
+        }
+
+        Document {
+            html: Html::new(output.to_str().unwrap().to_owned()),
+            headings: renderer.headings,
+        }
+    }
+}
+
+/// Returns the corresponding hoedown extension, if the extension is part of the parser.
+fn hoedown_extension(extension: Extension) -> Option<hoedown::Extension> {
+    match extension {
+        Extension::Autolink => Some(hoedown::AUTOLINK),
+        Extension::FencedCode => Some(hoedown::FENCED_CODE),
+        Extension::Tables => Some(hoedown::TABLES),
+        Extension::Footnotes => Some(hoedown::FOOTNOTES),
+        Extension::Strikethrough => Some(hoedown::STRIKETHROUGH),
+        Extension::Superscript => Some(hoedown::SUPERSCRIPT),
+        Extension::Highlight => Some(hoedown::HIGHLIGHT),
+        Extension::Smartypants | Extension::Emoji => None,
+    }
+}
+
+/// Extends the HTML renderer with heading anchors and syntax highlighting.
+///
+/// Raw HTML is escaped unless `raw_html` is set.
+struct AnchoredHtml {
+    html: hoedown::Html,
+    headings: Vec<Heading>,
+    ids: HeadingIds,
+    raw_html: bool,
+}
+
+impl Wrapper for AnchoredHtml {
+    type Base = hoedown::Html;
+
+    fn base(&mut self) -> &mut hoedown::Html {
+        &mut self.html
+    }
+
+    fn code_block(&mut self, output: &mut Buffer, code: Option<&Buffer>, lang: Option<&Buffer>) {
+        let code_str = code.and_then(|code| code.to_str().ok());
+        let language = lang.and_then(|lang| lang.to_str().ok());
+
+        if let (Some(code_str), Some(language)) = (code_str, language) {
+            if let Some(highlighted) = code_block_html(code_str, language) {
+                if !output.is_empty() {
+                    output.write_all(b"\n").unwrap();
+                }
+                output.write_all(highlighted.as_bytes()).unwrap();
+                output.write_all(b"\n").unwrap();
+                return;
+            }
+        }
+
+        self.html.code_block(output, code, lang);
+    }
+
+    fn html_block(&mut self, output: &mut Buffer, text: Option<&Buffer>) {
+        if self.raw_html {
+            self.html.html_block(output, text);
+        } else {
+            let mut escaped = Buffer::new(64);
+            self.html.normal_text(&mut escaped, text);
+            self.html.paragraph(output, Some(&escaped));
+        }
+    }
+
+    fn html_span(&mut self, output: &mut Buffer, text: Option<&Buffer>) -> bool {
+        if self.raw_html {
+            self.html.html_span(output, text)
+        } else {
+            self.html.normal_text(output, text);
+            true
+        }
+    }
+
+    fn header(&mut self, output: &mut Buffer, content: Option<&Buffer>, level: i32) {
+        let content = content.and_then(|c| c.to_str().ok()).unwrap_or_default();
+        let title = heading_title(content);
+        let id = self.ids.unique_id(&title);
+
+        if !output.is_empty() {
+            output.write_all(b"\n").unwrap();
+        }
+        output
+            .write_all(heading_html(level, &id, content).as_bytes())
+            .unwrap();
+        output.write_all(b"\n").unwrap();
+
+        self.headings.push(Heading { level, id, title });
+    }
+}
+
+wrap!(AnchoredHtml);
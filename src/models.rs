@@ -1,9 +1,9 @@
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use diesel::backend::Backend;
+use diesel::Queryable;
 use serde::Serialize;
 
-use crate::schema::{post_content, posts};
-
-use crate::blog::human_readable_format;
+use crate::schema::{aliases, categories, post_content, posts, tags};
 
 /// A post that has been parsed from markdown and is ready for insertion into the database.
 #[derive(Debug, Insertable)]
@@ -18,17 +18,71 @@ pub struct NewPost<'a> {
     /// The markdown of the post rendered as HTML.
     pub html: String,
 
-    /// A brief summary of the blog post.
+    /// A brief summary of the blog post, as HTML, followed by a link to the full post.
     ///
-    /// This is implemented as the first 300 lines of the blog post with HTML tags removed.
+    /// The summary is the `summary` field of the front matter if there is one, or else the
+    /// markdown before the `<!--more-->` marker, both rendered with their formatting intact.
+    /// Otherwise, it is the plain text at the start of the post, shortened at the end of a
+    /// sentence, or of a word if no sentence fits.
     pub summary: String,
 
     /// The URL to the blog post.
     pub url: String,
 
-    /// Used to disambiguate the blog post from others written the same day in the url. Created by
-    /// lower-casing the entire title and replacing spaces with dashes.
+    /// Used to disambiguate the blog post from others written the same day in the url. Taken from
+    /// the `slug` field of the front matter if there is one, and otherwise created from the title
+    /// by `slug::slugify`, which transliterates it to lowercase ASCII and joins its words with
+    /// dashes.
     pub slug: String,
+
+    /// Whether the post is a draft, unlisted, or public.
+    pub visibility: &'a str,
+
+    /// The name of the file that the post was parsed from, used to find the post again when the
+    /// file changes.
+    pub file_name: &'a str,
+
+    /// The number of words in the post.
+    pub word_count: i32,
+
+    /// The estimated number of minutes that it takes to read the post.
+    pub reading_time: i32,
+
+    /// The table of contents of the post, serialized as JSON, if the post should display one.
+    pub toc: Option<String>,
+}
+
+/// A tag attached to a blog post.
+#[derive(Debug, Insertable)]
+#[table_name = "tags"]
+pub struct NewTag<'a> {
+    /// The ID of the tagged post.
+    pub post_id: i32,
+
+    /// The name of the tag.
+    pub name: &'a str,
+}
+
+/// A path that redirects to a blog post.
+#[derive(Debug, Insertable)]
+#[table_name = "aliases"]
+pub struct NewAlias<'a> {
+    /// The path that should be redirected.
+    pub path: &'a str,
+
+    /// The ID of the post to redirect to.
+    pub post_id: i32,
+}
+
+/// A category that a blog post is filed under.
+#[derive(Debug, Insertable)]
+#[table_name = "categories"]
+pub struct NewCategory<'a> {
+    /// The ID of the categorized post.
+    pub post_id: i32,
+
+    /// The name of the category.
+    pub name: &'a str,
 }
 
 /// A blog post queried from the database.
@@ -39,6 +93,19 @@ pub struct Post {
     pub html: String,
     pub date: NaiveDateTime,
     pub url: String,
+    pub word_count: i32,
+    pub reading_time: i32,
+    pub toc: Option<String>,
+}
+
+/// The parts of a blog post that are syndicated in feeds.
+#[derive(Debug, Queryable)]
+pub struct FeedEntry {
+    pub title: String,
+    pub date: NaiveDateTime,
+    pub html: String,
+    pub summary: String,
+    pub url: String,
 }
 
 /// Used for full-text-search queries.
@@ -48,23 +115,66 @@ pub struct PostContent {
     pub docid: i32,
     pub title: String,
     pub content: String,
+    pub tags: String,
+    pub categories: String,
 }
 
 /// A brief summary of a blog post.
-#[derive(Debug, Serialize, Queryable)]
+#[derive(Debug, Serialize)]
 pub struct Summary {
+    /// The ID of the post.
+    #[serde(skip)]
+    pub id: i32,
+
     /// The title of the post.
     pub title: String,
 
     /// The date that the post was written.
-    #[serde(with = "human_readable_format")]
-    pub date: NaiveDateTime,
+    pub date: DateTime<Utc>,
 
     /// A short preview of the post.
     pub summary: String,
 
     /// A URL to reach the full post.
     pub url: String,
+
+    /// The number of words in the post.
+    pub word_count: i32,
+
+    /// The estimated number of minutes that it takes to read the post.
+    pub reading_time: i32,
+
+    /// The tags attached to the post.
+    pub tags: Vec<TermLink>,
+
+    /// The categories that the post is filed under.
+    pub categories: Vec<TermLink>,
+}
+
+// Tags and categories live in their own tables, so they are filled in after the summary itself has
+// been queried.
+impl<ST, DB> Queryable<ST, DB> for Summary
+where
+    DB: Backend,
+    (i32, String, NaiveDateTime, String, String, i32, i32): Queryable<ST, DB>,
+{
+    type Row = <(i32, String, NaiveDateTime, String, String, i32, i32) as Queryable<ST, DB>>::Row;
+
+    fn build(row: Self::Row) -> Self {
+        let (id, title, date, summary, url, word_count, reading_time) = Queryable::build(row);
+
+        Summary {
+            id,
+            title,
+            date: DateTime::from_utc(date, Utc),
+            summary,
+            url,
+            word_count,
+            reading_time,
+            tags: vec![],
+            categories: vec![],
+        }
+    }
 }
 
 /// Information needed to construct a link to a post.
@@ -76,3 +186,13 @@ pub struct PostLink {
     /// The URL linking to the post.
     pub url: String,
 }
+
+/// Information needed to construct a link to the archive of a tag or category.
+#[derive(Debug, Clone, Serialize)]
+pub struct TermLink {
+    /// The name of the tag or category.
+    pub name: String,
+
+    /// The URL linking to the archive.
+    pub url: String,
+}
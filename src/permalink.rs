@@ -0,0 +1,211 @@
+//! The scheme used to build the URLs of blog posts.
+//!
+//! A permalink pattern is a path whose segments are either literal text or one of the following
+//! placeholders:
+//!
+//! - `:year`: The year that the post was written, such as `2014`.
+//! - `:month`: The month that the post was written, without zero padding.
+//! - `:day`: The day of the month that the post was written, without zero padding.
+//! - `:slug`: The slug of the post. Every pattern must contain this placeholder.
+//!
+//! Dates are taken from the configured time zone.
+//!
+//! A pattern may not have the same shape as the route of another page, such as `/blog/:year/:slug`
+//! and the `/blog/:year/:month` archive, because the router could not tell them apart.
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike};
+use chrono_tz::Tz;
+use serde::de::{self, Deserialize, Deserializer};
+
+use crate::errors::*;
+use crate::routes;
+
+/// The permalink pattern that is used if none is configured.
+pub const DEFAULT_PATTERN: &str = "/blog/:year/:month/:day/:slug";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Year,
+    Month,
+    Day,
+    Slug,
+}
+
+/// A parsed permalink pattern.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Permalink {
+    pattern: String,
+    segments: Vec<Segment>,
+}
+
+impl Permalink {
+    /// Returns the pattern in a form that is suitable for registering with the router.
+    ///
+    /// Each placeholder becomes a route parameter of the same name.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Returns the canonical URL of a post written at the given time with the given slug.
+    pub fn format(&self, date: &DateTime<Tz>, slug: &str) -> String {
+        let mut url = String::new();
+
+        for segment in &self.segments {
+            url.push('/');
+            match *segment {
+                Segment::Literal(ref literal) => url.push_str(literal),
+                Segment::Year => url.push_str(&date.year().to_string()),
+                Segment::Month => url.push_str(&date.month().to_string()),
+                Segment::Day => url.push_str(&date.day().to_string()),
+                Segment::Slug => url.push_str(slug),
+            }
+        }
+
+        url
+    }
+}
+
+impl Default for Permalink {
+    fn default() -> Self {
+        DEFAULT_PATTERN.parse().unwrap()
+    }
+}
+
+impl FromStr for Permalink {
+    type Err = Error;
+
+    fn from_str(pattern: &str) -> Result<Self> {
+        if !pattern.starts_with('/') {
+            bail!("permalink `{}` must start with `/`", pattern);
+        }
+
+        let raw_segments = pattern
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>();
+
+        let segments = raw_segments
+            .iter()
+            .map(|&segment| {
+                Ok(match segment {
+                    ":year" => Segment::Year,
+                    ":month" => Segment::Month,
+                    ":day" => Segment::Day,
+                    ":slug" => Segment::Slug,
+                    _ if segment.contains(|c| c == ':' || c == '*') => {
+                        bail!(
+                            "unknown placeholder `{}` in permalink `{}`",
+                            segment,
+                            pattern
+                        )
+                    }
+                    _ => Segment::Literal(segment.to_owned()),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if !segments.contains(&Segment::Slug) {
+            bail!("permalink `{}` must contain `:slug`", pattern);
+        }
+
+        if let Some(glob) = routes::page_globs().find(|glob| conflicts(&segments, glob)) {
+            bail!(
+                "permalink `{}` conflicts with the route of another page, `{}`",
+                pattern,
+                glob
+            );
+        }
+
+        Ok(Permalink {
+            pattern: format!("/{}", raw_segments.join("/")),
+            segments,
+        })
+    }
+}
+
+/// Returns whether the router could not tell a pattern apart from a route glob, because they have
+/// the same literal segments and their placeholders are in the same places.
+fn conflicts(segments: &[Segment], glob: &str) -> bool {
+    let glob_segments = glob
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+
+    glob_segments.len() == segments.len()
+        && segments
+            .iter()
+            .zip(glob_segments)
+            .all(|(segment, glob)| match *segment {
+                Segment::Literal(ref literal) => literal == glob,
+                _ => glob.starts_with(':'),
+            })
+}
+
+impl fmt::Display for Permalink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.pattern.fmt(f)
+    }
+}
+
+impl<'de> Deserialize<'de> for Permalink {
+    fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let pattern = String::deserialize(deserializer)?;
+        pattern.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use chrono_tz::America::Los_Angeles;
+
+    use super::Permalink;
+
+    #[test]
+    fn format() {
+        let date = Los_Angeles.ymd(2014, 5, 9).and_hms(20, 39, 0);
+
+        let permalink = Permalink::default();
+        assert_eq!(
+            permalink.format(&date, "patching-vim"),
+            "/blog/2014/5/9/patching-vim"
+        );
+
+        let permalink = "/posts/:year/:slug/".parse::<Permalink>().unwrap();
+        assert_eq!(
+            permalink.format(&date, "patching-vim"),
+            "/posts/2014/patching-vim"
+        );
+        assert_eq!(permalink.pattern(), "/posts/:year/:slug");
+    }
+
+    #[test]
+    fn invalid_patterns() {
+        assert!("blog/:slug".parse::<Permalink>().is_err());
+        assert!("/blog/:year/:title".parse::<Permalink>().is_err());
+        assert!("/blog/:year".parse::<Permalink>().is_err());
+        assert!("/blog/*rest/:slug".parse::<Permalink>().is_err());
+    }
+
+    #[test]
+    fn conflicting_patterns() {
+        let error = "/blog/:year/:slug".parse::<Permalink>().unwrap_err();
+        assert!(
+            error.to_string().contains("/blog/:year/:month"),
+            "{}",
+            error
+        );
+
+        assert!("/blog/:slug".parse::<Permalink>().is_err());
+        assert!("/blog/tags/:slug".parse::<Permalink>().is_err());
+        assert!("/blog/posts/:slug".parse::<Permalink>().is_ok());
+        assert!("/:year/:slug".parse::<Permalink>().is_ok());
+    }
+}
@@ -38,6 +38,7 @@ impl Key for DatabaseConnectionPool {
 }
 
 /// A connection pool for maintaining multiple database connections.
+#[derive(Clone)]
 pub struct ConnectionPool(Pool<ConnectionManager<SqliteConnection>>);
 
 impl Deref for ConnectionPool {
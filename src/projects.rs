@@ -13,7 +13,9 @@ use tokio::runtime::Runtime;
 use url::Url;
 use url_serde;
 
+use crate::config::Config;
 use crate::errors::*;
+use crate::links;
 use crate::markdown::{self, Html, Markdown};
 
 /// Encapsulates a project that I have worked on.
@@ -27,8 +29,18 @@ pub struct Project {
     url: Url,
 }
 
+impl Project {
+    /// Returns the name of the project.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
 /// Returns a list of projects parsed from a file.
-pub fn load<P>(projects_path: P) -> Result<Vec<Project>>
+///
+/// Project descriptions are rendered with the configured markdown backend and extensions, then
+/// sanitized.
+pub fn load<P>(projects_path: P, config: &Config) -> Result<Vec<Project>>
 where
     P: AsRef<Path>,
 {
@@ -69,7 +81,14 @@ where
 
             let description = {
                 let description = &parsed_project.description;
-                markdown::render_html(description.deref())
+                let html = markdown::render_html(
+                    description.deref(),
+                    config.markdown_backend,
+                    &config.markdown_extensions,
+                    config.sanitize.raw_html.projects,
+                );
+                let html = config.sanitize.clean(&html);
+                links::decorate(&html, &config.links, &config.site_url)
             };
 
             Ok(Project {
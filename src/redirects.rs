@@ -0,0 +1,211 @@
+//! Site-wide redirects for pages that have moved.
+//!
+//! Redirects are read from a YAML file containing a list of rules. Each rule maps a path that used
+//! to exist to the path that should be served instead:
+//!
+//! ```yaml
+//! - from: /blog/2014/5/9/patching-vim
+//!   to: /blog/2014/5/9/patching-neovim
+//! - from: /posts/:slug
+//!   to: /blog/:slug
+//! - from: /files/*rest
+//!   to: /static/:rest
+//! ```
+//!
+//! A segment starting with `:` matches any single path segment, and a final segment starting with
+//! `*` matches the rest of the path. Matched segments may be substituted into the target by name.
+//! Rules without any such segments must match the path exactly, and take priority over patterns.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+
+use log::*;
+use serde::Deserialize;
+use serde_yaml;
+
+use crate::errors::*;
+
+/// A single redirect, as written in the redirects file.
+#[derive(Debug, Deserialize)]
+struct Rule {
+    from: String,
+    to: String,
+}
+
+/// A path pattern that may capture segments of the requested path.
+#[derive(Debug)]
+struct Pattern {
+    segments: Vec<String>,
+    to: String,
+}
+
+impl Pattern {
+    /// Returns the target of the redirect if the path matches the pattern.
+    fn redirect(&self, path: &str) -> Option<String> {
+        let mut path_segments = segments(path);
+        let mut captures = HashMap::new();
+
+        for segment in &self.segments {
+            if segment.starts_with('*') {
+                let rest = path_segments.collect::<Vec<_>>().join("/");
+                captures.insert(&segment[1..], rest);
+                return Some(substitute(&self.to, &captures));
+            }
+
+            let path_segment = path_segments.next()?;
+            if segment.starts_with(':') {
+                captures.insert(&segment[1..], path_segment.to_owned());
+            } else if segment != path_segment {
+                return None;
+            }
+        }
+
+        if path_segments.next().is_some() {
+            return None;
+        }
+
+        Some(substitute(&self.to, &captures))
+    }
+}
+
+/// The set of redirects that apply to the whole website.
+#[derive(Debug, Default)]
+pub struct Redirects {
+    exact: HashMap<String, String>,
+    patterns: Vec<Pattern>,
+}
+
+impl Redirects {
+    /// Returns the path that a request for `path` should be redirected to, if any.
+    pub fn lookup(&self, path: &str) -> Option<String> {
+        let path = normalize(path);
+
+        if let Some(to) = self.exact.get(&path) {
+            return Some(to.to_owned());
+        }
+
+        self.patterns
+            .iter()
+            .filter_map(|pattern| pattern.redirect(&path))
+            .next()
+    }
+}
+
+/// Returns the canonical form of a path, so that trailing slashes do not affect matching.
+pub fn normalize(path: &str) -> String {
+    format!("/{}", segments(path).collect::<Vec<_>>().join("/"))
+}
+
+fn segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|segment| !segment.is_empty())
+}
+
+/// Replaces each `:name` segment in a target path with the captured segment of the same name.
+fn substitute(to: &str, captures: &HashMap<&str, String>) -> String {
+    to.split('/')
+        .map(|segment| {
+            if segment.starts_with(':') {
+                captures
+                    .get(&segment[1..])
+                    .map(String::as_str)
+                    .unwrap_or(segment)
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn parse_redirects<R>(reader: R) -> Result<Redirects>
+where
+    R: Read,
+{
+    let rules: Vec<Rule> = serde_yaml::from_reader(reader)?;
+
+    let mut redirects = Redirects::default();
+    for rule in rules {
+        let segments = segments(&rule.from).map(String::from).collect::<Vec<_>>();
+
+        if let Some(position) = segments.iter().position(|s| s.starts_with('*')) {
+            if position != segments.len() - 1 {
+                bail!("`*` may only be used in the last segment of {}", rule.from);
+            }
+        }
+
+        if segments
+            .iter()
+            .any(|s| s.starts_with(':') || s.starts_with('*'))
+        {
+            redirects.patterns.push(Pattern {
+                segments,
+                to: rule.to,
+            });
+        } else {
+            redirects.exact.insert(normalize(&rule.from), rule.to);
+        }
+    }
+
+    Ok(redirects)
+}
+
+/// Load the redirects from a path.
+pub fn load<P>(redirects_path: P) -> Result<Redirects>
+where
+    P: AsRef<Path>,
+{
+    info!("loading redirects from {:?}", redirects_path.as_ref());
+    let redirects_file =
+        File::open(&redirects_path).chain_err(|| "error opening redirects file")?;
+    parse_redirects(redirects_file)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn lookup() {
+        let redirects = super::parse_redirects(
+            r#"
+- from: /old-page/
+  to: /about
+- from: /posts/:slug
+  to: /blog/:slug
+- from: /posts/archive
+  to: /blog
+- from: /files/*rest
+  to: /static/:rest
+"#
+            .as_bytes(),
+        )
+        .unwrap();
+
+        assert_eq!(redirects.lookup("/old-page"), Some(String::from("/about")));
+        assert_eq!(redirects.lookup("/old-page/"), Some(String::from("/about")));
+        assert_eq!(
+            redirects.lookup("/posts/archive"),
+            Some(String::from("/blog"))
+        );
+        assert_eq!(
+            redirects.lookup("/posts/patching-vim"),
+            Some(String::from("/blog/patching-vim"))
+        );
+        assert_eq!(
+            redirects.lookup("/files/images/me.jpg"),
+            Some(String::from("/static/images/me.jpg"))
+        );
+        assert_eq!(redirects.lookup("/posts/patching-vim/comments"), None);
+        assert_eq!(redirects.lookup("/about"), None);
+    }
+
+    #[test]
+    fn invalid_glob() {
+        assert!(super::parse_redirects("- { from: /files/*rest/x, to: / }".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn load_redirects() {
+        super::load("redirects.yaml").unwrap();
+    }
+}
@@ -3,11 +3,16 @@
 use std::error::Error;
 use std::fs;
 use std::path::Path;
+use std::str::FromStr;
 use std::sync::Arc;
 
 use chrono::NaiveDate;
+use chrono_tz::Tz;
 use diesel;
 use handlebars_iron::{DirectorySource, HandlebarsEngine, Template};
+use iron::headers::Location;
+use iron::mime::Mime;
+use iron::modifiers::Header;
 use iron::prelude::*;
 use iron::status;
 use iron::{iexpect, itry, AfterMiddleware, Handler};
@@ -18,19 +23,27 @@ use persistent::{self, Read};
 use router::{router, NoRoute, Router};
 use serde_json::{self, json};
 use staticfile::Static;
+use url::form_urlencoded;
+use url::percent_encoding::percent_decode;
 
 #[cfg(feature = "watch")]
 use handlebars_iron::Watchable;
 
-use crate::blog;
+use crate::blog::{self, PostQuery, Taxonomy};
 use crate::config;
 use crate::errors::*;
+use crate::feed::{Feed, Format};
 use crate::helpers;
+use crate::highlight;
 use crate::persistence::{Config, ConnectionPool, DatabaseConnectionPool, Projects};
 use crate::projects::Project;
+use crate::redirects::Redirects;
 
 /// The number of blog post summaries that should be displayed.
-const NUM_SUMMARIES: usize = 3;
+const NUM_SUMMARIES: i64 = 3;
+
+/// The number of blog post summaries that should be displayed on each page of the blog.
+const SUMMARIES_PER_PAGE: i64 = 10;
 
 fn resume(req: &mut Request<'_, '_>) -> IronResult<Response> {
     let data = json!({
@@ -59,13 +72,37 @@ fn blog_post(req: &mut Request<'_, '_>) -> IronResult<Response> {
         .unwrap();
     let params = req.extensions.get::<Router>().unwrap();
 
-    let year = iexpect!(params.find("year").and_then(|y| y.parse().ok()));
-    let month = iexpect!(params.find("month").and_then(|m| m.parse().ok()));
-    let day = iexpect!(params.find("day").and_then(|d| d.parse().ok()));
-    let slug = iexpect!(req.extensions.get::<Router>().unwrap().find("slug"));
+    // Only the parameters that appear in the configured permalink pattern are present.
+    let post_query = PostQuery {
+        year: iexpect!(numeric_param(params, "year")),
+        month: iexpect!(numeric_param(params, "month")),
+        day: iexpect!(numeric_param(params, "day")),
+        slug: iexpect!(params.find("slug")).to_owned(),
+    };
+
+    if let PostQuery {
+        year: Some(year),
+        month: Some(month),
+        day: Some(day),
+        ..
+    } = post_query
+    {
+        iexpect!(NaiveDate::from_ymd_opt(year, month, day));
+    }
+
+    let config = req.get::<Read<Config>>().unwrap();
 
-    let date = iexpect!(NaiveDate::from_ymd_opt(year, month, day));
-    let post = match blog::get_post(&connection, &date, slug) {
+    let preview = match (
+        req.get_ref::<Params>().unwrap().find(&["preview"]),
+        &config.preview_token,
+    ) {
+        (Some(&Value::String(ref given)), &Some(ref token)) => {
+            constant_time_eq(given.as_bytes(), token.as_bytes())
+        }
+        _ => false,
+    };
+
+    let post = match blog::get_post(&connection, &post_query, preview, config.timezone) {
         Ok(post) => post,
         Err(Error(ErrorKind::Sql(diesel::NotFound), _)) => {
             return Err(IronError::new(NoRoute, status::NotFound))
@@ -73,12 +110,51 @@ fn blog_post(req: &mut Request<'_, '_>) -> IronResult<Response> {
         Err(e) => return Err(IronError::new(e, status::NotFound)),
     };
 
+    // The same post may be reachable from several URLs, such as ones with zero-padded dates.
+    if request_path(req) != post.url {
+        let location = match req.url.query() {
+            Some(query) => format!("{}?{}", post.url, query),
+            None => post.url,
+        };
+        return Ok(Response::with((
+            status::MovedPermanently,
+            Header(Location(location)),
+        )));
+    }
+
     Ok(Response::with((
         status::Ok,
         Template::new("blog_post", post),
     )))
 }
 
+/// Parses an optional numeric route parameter.
+///
+/// Returns `None` if the parameter is present but is not a valid number.
+fn numeric_param<T>(params: &router::Params, name: &str) -> Option<Option<T>>
+where
+    T: FromStr,
+{
+    match params.find(name) {
+        Some(value) => value.parse().ok().map(Some),
+        None => Some(None),
+    }
+}
+
+/// Compares two byte strings in a time that depends only on their lengths, so that a secret such
+/// as the preview token cannot be guessed one character at a time.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Returns the percent-decoded path of a request.
+fn request_path(req: &Request<'_, '_>) -> String {
+    let path = format!("/{}", req.url.path().join("/"));
+    percent_decode(path.as_bytes())
+        .decode_utf8_lossy()
+        .into_owned()
+}
+
 fn blog(req: &mut Request<'_, '_>) -> IronResult<Response> {
     let connection = req
         .get::<Read<DatabaseConnectionPool>>()
@@ -91,21 +167,245 @@ fn blog(req: &mut Request<'_, '_>) -> IronResult<Response> {
         _ => None,
     };
 
+    let page = page_param(req)?;
     let summaries = if let Some(ref query) = query {
-        blog::find_summaries(&connection, query)
+        let config = req.get::<Read<Config>>().unwrap();
+        blog::find_summaries(&connection, query, &config, page, SUMMARIES_PER_PAGE)
     } else {
-        blog::get_summaries(&connection)
+        blog::get_summaries(&connection, page, SUMMARIES_PER_PAGE)
     };
+    let mut data = itry!(serde_json::to_value(not_found_if_missing(summaries)?));
 
-    let mut data = json!({ "posts": itry!(summaries) });
-
-    if let Some(query) = query.and_then(|query| serde_json::to_value(query).ok()) {
-        data["query"] = query;
+    if let Some(query) = query {
+        // Pagination links must search for the same query.
+        data["page_query"] = json!(form_urlencoded::Serializer::new(String::new())
+            .append_pair("q", &query)
+            .finish());
+        data["query"] = json!(query);
     }
 
     Ok(Response::with((status::Ok, Template::new("blog", data))))
 }
 
+fn year_archive(req: &mut Request<'_, '_>) -> IronResult<Response> {
+    date_archive(req)
+}
+
+fn month_archive(req: &mut Request<'_, '_>) -> IronResult<Response> {
+    date_archive(req)
+}
+
+/// Lists the posts written during a year, or during a month if the route contains one.
+fn date_archive(req: &mut Request<'_, '_>) -> IronResult<Response> {
+    let connection = req
+        .get::<Read<DatabaseConnectionPool>>()
+        .unwrap()
+        .get()
+        .unwrap();
+    let timezone = req.get::<Read<Config>>().unwrap().timezone;
+
+    let (year, month) = {
+        let params = req.extensions.get::<Router>().unwrap();
+        match (
+            numeric_param(params, "year").and_then(|year| year),
+            numeric_param(params, "month"),
+        ) {
+            (Some(year), Some(month)) => (year, month),
+            _ => return Err(IronError::new(NoRoute, status::NotFound)),
+        }
+    };
+    let page = page_param(req)?;
+
+    let summaries = not_found_if_missing(blog::get_archive(
+        &connection,
+        year,
+        month,
+        timezone,
+        page,
+        SUMMARIES_PER_PAGE,
+    ))?;
+
+    let title = match month {
+        Some(month) => NaiveDate::from_ymd(year, month, 1)
+            .format("%B %Y")
+            .to_string(),
+        None => year.to_string(),
+    };
+
+    let mut data = itry!(serde_json::to_value(summaries));
+    data["title"] = json!(title);
+
+    Ok(Response::with((
+        status::Ok,
+        Template::new("blog_date_archive", data),
+    )))
+}
+
+/// Returns the page number requested by the `page` query parameter, defaulting to the first page.
+fn page_param(req: &mut Request<'_, '_>) -> IronResult<i64> {
+    match req.get_ref::<Params>().unwrap().find(&["page"]) {
+        Some(&Value::String(ref page)) => page
+            .parse()
+            .map_err(|_| IronError::new(NoRoute, status::NotFound)),
+        Some(_) => Err(IronError::new(NoRoute, status::NotFound)),
+        None => Ok(1),
+    }
+}
+
+/// Converts a missing row into a "not found" response, leaving other errors as they are.
+fn not_found_if_missing<T>(result: Result<T>) -> IronResult<T> {
+    match result {
+        Ok(value) => Ok(value),
+        Err(Error(ErrorKind::Sql(diesel::NotFound), _)) => {
+            Err(IronError::new(NoRoute, status::NotFound))
+        }
+        Err(e) => Err(IronError::new(e, status::InternalServerError)),
+    }
+}
+
+fn tags(req: &mut Request<'_, '_>) -> IronResult<Response> {
+    terms(req, Taxonomy::Tag)
+}
+
+fn tag(req: &mut Request<'_, '_>) -> IronResult<Response> {
+    term_archive(req, Taxonomy::Tag)
+}
+
+fn categories(req: &mut Request<'_, '_>) -> IronResult<Response> {
+    terms(req, Taxonomy::Category)
+}
+
+fn category(req: &mut Request<'_, '_>) -> IronResult<Response> {
+    term_archive(req, Taxonomy::Category)
+}
+
+/// Lists every term of a taxonomy along with the number of posts filed under it.
+fn terms(req: &mut Request<'_, '_>, taxonomy: Taxonomy) -> IronResult<Response> {
+    let connection = req
+        .get::<Read<DatabaseConnectionPool>>()
+        .unwrap()
+        .get()
+        .unwrap();
+
+    let terms = itry!(blog::get_term_counts(&connection, taxonomy));
+
+    let data = json!({
+        "taxonomy": taxonomy.path(),
+        "terms": terms,
+    });
+    Ok(Response::with((
+        status::Ok,
+        Template::new("blog_terms", data),
+    )))
+}
+
+/// Lists summaries of every post filed under a single term of a taxonomy.
+fn term_archive(req: &mut Request<'_, '_>, taxonomy: Taxonomy) -> IronResult<Response> {
+    let connection = req
+        .get::<Read<DatabaseConnectionPool>>()
+        .unwrap()
+        .get()
+        .unwrap();
+    let term = iexpect!(term_param(req));
+
+    let summaries = itry!(blog::find_summaries_by_term(&connection, taxonomy, &term));
+    if summaries.is_empty() {
+        return Err(IronError::new(NoRoute, status::NotFound));
+    }
+
+    let data = json!({
+        "taxonomy": taxonomy.path(),
+        "term": term,
+        "url": taxonomy.link(&term).url,
+        "posts": summaries,
+    });
+    Ok(Response::with((
+        status::Ok,
+        Template::new("blog_archive", data),
+    )))
+}
+
+/// Returns the percent-decoded tag or category matched by the route.
+fn term_param(req: &Request<'_, '_>) -> Option<String> {
+    req.extensions
+        .get::<Router>()
+        .unwrap()
+        .find("term")
+        .and_then(|term| percent_decode(term.as_bytes()).decode_utf8().ok())
+        .map(|term| term.into_owned())
+}
+
+fn atom_feed(req: &mut Request<'_, '_>) -> IronResult<Response> {
+    feed(req, Format::Atom, None)
+}
+
+fn rss_feed(req: &mut Request<'_, '_>) -> IronResult<Response> {
+    feed(req, Format::Rss, None)
+}
+
+fn tag_atom_feed(req: &mut Request<'_, '_>) -> IronResult<Response> {
+    feed(req, Format::Atom, Some(Taxonomy::Tag))
+}
+
+fn tag_rss_feed(req: &mut Request<'_, '_>) -> IronResult<Response> {
+    feed(req, Format::Rss, Some(Taxonomy::Tag))
+}
+
+fn category_atom_feed(req: &mut Request<'_, '_>) -> IronResult<Response> {
+    feed(req, Format::Atom, Some(Taxonomy::Category))
+}
+
+fn category_rss_feed(req: &mut Request<'_, '_>) -> IronResult<Response> {
+    feed(req, Format::Rss, Some(Taxonomy::Category))
+}
+
+/// Syndicates the most recent blog posts, optionally restricted to a single tag or category.
+fn feed(
+    req: &mut Request<'_, '_>,
+    format: Format,
+    taxonomy: Option<Taxonomy>,
+) -> IronResult<Response> {
+    let connection = req
+        .get::<Read<DatabaseConnectionPool>>()
+        .unwrap()
+        .get()
+        .unwrap();
+    let site_url = req.get::<Read<Config>>().unwrap().site_url.clone();
+
+    let feed = match taxonomy {
+        Some(taxonomy) => {
+            let term = iexpect!(term_param(req));
+            let entries = itry!(blog::get_feed_entries(&connection, Some((taxonomy, &term))));
+            if entries.is_empty() {
+                return Err(IronError::new(NoRoute, status::NotFound));
+            }
+
+            let title = format!("acrussell.com | {}", term);
+            let path = taxonomy.link(&term).url;
+            itry!(Feed::new(&site_url, &title, &path, format, entries))
+        }
+        None => {
+            let entries = itry!(blog::get_feed_entries(&connection, None));
+            itry!(Feed::new(
+                &site_url,
+                "acrussell.com",
+                "/blog",
+                format,
+                entries
+            ))
+        }
+    };
+
+    let mime = format!("{}; charset=utf-8", format.media_type())
+        .parse::<Mime>()
+        .unwrap();
+    Ok(Response::with((
+        status::Ok,
+        mime,
+        Template::new(format.template(), feed),
+    )))
+}
+
 fn about(_: &mut Request<'_, '_>) -> IronResult<Response> {
     let images = Path::new("static/images/slideshow");
     let image_urls = itry!(fs::read_dir(images))
@@ -125,27 +425,137 @@ fn index(req: &mut Request<'_, '_>) -> IronResult<Response> {
         .unwrap()
         .get()
         .unwrap();
-    let posts = itry!(blog::get_summaries(&connection));
+    let summaries = itry!(blog::get_summaries(&connection, 1, NUM_SUMMARIES));
 
-    let data = json!({
-        "posts": posts.into_iter().take(NUM_SUMMARIES).collect::<Vec<_>>()
-    });
+    let data = json!({ "posts": summaries.posts });
     Ok(Response::with((status::Ok, Template::new("index", data))))
 }
 
-/// Returns the router for the server.
-fn get_router() -> Router {
-    router!(
-        index:      get "/" => index,
-        about:      get "/about" => about,
-        blog:       get "/blog" => blog,
-        blog_post:  get "/blog/:year/:month/:day/:slug" => blog_post,
-        projects:   get "/projects" => projects,
-        resume:     get "/resume" => resume,
+type PageHandler = fn(&mut Request<'_, '_>) -> IronResult<Response>;
+
+/// The pages of the website that are rendered by a handler, by route ID and glob.
+const PAGES: &[(&str, &str, PageHandler)] = &[
+    ("index", "/", index),
+    ("about", "/about", about),
+    ("blog", "/blog", blog),
+    ("tags", "/blog/tags", tags),
+    ("tag", "/blog/tags/:term", tag),
+    ("categories", "/blog/categories", categories),
+    ("category", "/blog/categories/:term", category),
+    ("year_archive", "/blog/:year", year_archive),
+    ("month_archive", "/blog/:year/:month", month_archive),
+    ("atom_feed", "/blog/feed.atom", atom_feed),
+    ("rss_feed", "/blog/feed.rss", rss_feed),
+    ("tag_atom_feed", "/blog/tags/:term/feed.atom", tag_atom_feed),
+    ("tag_rss_feed", "/blog/tags/:term/feed.rss", tag_rss_feed),
+    (
+        "category_atom_feed",
+        "/blog/categories/:term/feed.atom",
+        category_atom_feed,
+    ),
+    (
+        "category_rss_feed",
+        "/blog/categories/:term/feed.rss",
+        category_rss_feed,
+    ),
+    ("projects", "/projects", projects),
+    ("resume", "/resume", resume),
+];
+
+/// Returns the globs of the pages of the website that are rendered by a handler.
+pub fn page_globs() -> impl Iterator<Item = &'static str> {
+    PAGES.iter().map(|&(_, glob, _)| glob)
+}
+
+/// Files that are served from the root of the website rather than from `/static`.
+const ROOT_FILES: &[&str] = &["/favicon.ico", "/robots.txt", "/highlight.css"];
+
+/// Returns whether a path is served by one of the pages of the website, other than a blog post,
+/// or is a static file.
+///
+/// Archives must be for a valid year or month, or for a tag or category for which `is_term`
+/// returns true. Blog posts are not included, because the permalink pattern matches posts that do
+/// not exist.
+pub fn is_page<F>(path: &str, is_term: F) -> bool
+where
+    F: Fn(Taxonomy, &str) -> bool,
+{
+    let path = path.trim_end_matches('/');
+    let path = if path.is_empty() { "/" } else { path };
+
+    path.starts_with("/static/")
+        || ROOT_FILES.contains(&path)
+        || PAGES
+            .iter()
+            .any(|&(_, glob, _)| match match_glob(glob, path) {
+                Some(params) => params.iter().all(|&(name, value)| match name {
+                    "year" => value.parse::<i32>().is_ok(),
+                    "month" => value
+                        .parse::<u32>()
+                        .map_or(false, |month| month >= 1 && month <= 12),
+                    "term" => {
+                        let term = percent_decode(value.as_bytes()).decode_utf8_lossy();
+                        [Taxonomy::Tag, Taxonomy::Category]
+                            .iter()
+                            .find(|taxonomy| {
+                                glob.starts_with(&format!("/blog/{}/", taxonomy.path()))
+                            })
+                            .map_or(false, |&taxonomy| is_term(taxonomy, &term))
+                    }
+                    _ => true,
+                }),
+                None => false,
+            })
+}
+
+/// Matches a path against a route glob, where segments starting with `:` match any segment.
+///
+/// Returns the name of each parameter in the glob along with the segment that it matched.
+fn match_glob<'a>(glob: &'a str, path: &'a str) -> Option<Vec<(&'a str, &'a str)>> {
+    let glob_segments = glob.split('/').collect::<Vec<_>>();
+    let path_segments = path.split('/').collect::<Vec<_>>();
+    if glob_segments.len() != path_segments.len() {
+        return None;
+    }
 
+    let mut params = vec![];
+    for (glob, segment) in glob_segments.into_iter().zip(path_segments) {
+        match glob.strip_prefix(':') {
+            Some(name) if !segment.is_empty() => params.push((name, segment)),
+            None if glob == segment => (),
+            _ => return None,
+        }
+    }
+
+    Some(params)
+}
+
+/// Returns the router for the server.
+///
+/// Blog posts are routed according to the permalink pattern.
+fn get_router(config: &config::Config) -> Result<Router> {
+    let mut router = router!(
         favicon:    get "/favicon.ico" => Static::new(Path::new("static/images")),
         robots_txt: get "/robots.txt" => Static::new(Path::new("static")),
-    )
+    );
+
+    for &(id, glob, handler) in PAGES {
+        router.get(glob, handler, id);
+    }
+
+    router.get(config.permalink.pattern(), blog_post, "blog_post");
+
+    let highlight_css = highlight::stylesheet(&config.highlight_theme)?;
+    router.get(
+        "/highlight.css",
+        move |_: &mut Request<'_, '_>| {
+            let mime = "text/css; charset=utf-8".parse::<Mime>().unwrap();
+            Ok(Response::with((status::Ok, mime, highlight_css.clone())))
+        },
+        "highlight_css",
+    );
+
+    Ok(router)
 }
 
 #[cfg(feature = "watch")]
@@ -156,12 +566,18 @@ fn watch_templates(hbse: Arc<HandlebarsEngine>, path: &str) {
 #[cfg(not(feature = "watch"))]
 fn watch_templates(_hbse: Arc<HandlebarsEngine>, _path: &str) {}
 
-fn initialize_templates(folder: &str, extension: &str) -> Result<Arc<HandlebarsEngine>> {
+fn initialize_templates(
+    folder: &str,
+    extension: &str,
+    timezone: Tz,
+) -> Result<Arc<HandlebarsEngine>> {
     let hbse = {
         let mut hbse = HandlebarsEngine::new();
         hbse.add(Box::new(DirectorySource::new(folder, extension)));
         hbse.handlebars_mut()
             .register_helper("join", Box::new(helpers::join));
+        hbse.handlebars_mut()
+            .register_helper("format_date", Box::new(helpers::FormatDate::new(timezone)));
         hbse.reload().chain_err(|| "could not reload templates")?;
 
         Arc::new(hbse)
@@ -185,24 +601,28 @@ fn mount(chain: Chain) -> Mount {
 ///   - Routing for all page requests
 ///   - Routing for static files
 ///   - Persistence for the website configuration
+///   - Redirects for moved pages
 ///   - Rendering handlebars templates
 ///   - Error reporting
 ///   - Error handling
 pub fn handler(
     config: config::Config,
     projects: Vec<Project>,
+    redirects: Redirects,
     connection_pool: ConnectionPool,
 ) -> Result<Box<dyn Handler>> {
-    let mut chain = Chain::new(get_router());
+    let mut chain = Chain::new(get_router(&config)?);
 
+    let timezone = config.timezone;
     chain.link_before(persistent::Read::<Config>::one(config));
     chain.link_before(persistent::Read::<Projects>::one(projects));
     chain.link_before(persistent::Read::<DatabaseConnectionPool>::one(
         connection_pool,
     ));
 
+    chain.link_after(Redirector(redirects));
     chain.link_after(ErrorHandler);
-    chain.link_after(initialize_templates("./templates/", ".hbs")?);
+    chain.link_after(initialize_templates("./templates/", ".hbs", timezone)?);
     chain.link_after(ErrorReporter);
 
     let mount = mount(chain);
@@ -218,6 +638,42 @@ impl AfterMiddleware for ErrorReporter {
     }
 }
 
+/// Permanently redirects requests for pages that do not exist, if the page has moved.
+///
+/// Aliases of blog posts are checked before the site-wide redirects.
+struct Redirector(Redirects);
+
+impl AfterMiddleware for Redirector {
+    fn catch(&self, req: &mut Request<'_, '_>, err: IronError) -> IronResult<Response> {
+        if err.error.downcast::<NoRoute>().is_none() {
+            return Err(err);
+        }
+
+        let path = request_path(req);
+
+        let connection = req
+            .get::<Read<DatabaseConnectionPool>>()
+            .unwrap()
+            .get()
+            .unwrap();
+        let alias = match blog::find_alias(&connection, &path) {
+            Ok(alias) => alias,
+            Err(e) => {
+                error!("could not look up alias {}: {}", path, e);
+                None
+            }
+        };
+
+        match alias.or_else(|| self.0.lookup(&path)) {
+            Some(location) => Ok(Response::with((
+                status::MovedPermanently,
+                Header(Location(location)),
+            ))),
+            None => Err(err),
+        }
+    }
+}
+
 struct ErrorHandler;
 
 impl AfterMiddleware for ErrorHandler {
@@ -243,12 +699,15 @@ mod tests {
     use std::io::prelude::*;
 
     use diesel::connection::SimpleConnection;
+    use iron::headers::{ContentType, Location};
+    use iron::status;
     use iron::{Handler, Headers};
 
     use self::iron_test::{request, response};
     use self::tempfile::NamedTempFile;
     use self::url::Url;
 
+    use crate::blog::Taxonomy;
     use crate::config::Config;
     use crate::persistence;
 
@@ -277,13 +736,30 @@ mod tests {
         };
 
         connection.batch_execute(&schema).unwrap();
-        crate::blog::create_fts_index(&connection).unwrap();
+        crate::blog::load(
+            "blog/",
+            &connection,
+            &crate::config::load("config.yaml").unwrap(),
+            &crate::shortcodes::Shortcodes::load("templates", &[]).unwrap(),
+        )
+        .unwrap();
 
         let handler = super::handler(
             Config {
+                site_url: Url::parse("https://acrussell.com").unwrap(),
+                timezone: chrono_tz::America::Los_Angeles,
+                permalink: Default::default(),
+                highlight_theme: String::from("base16-ocean.dark"),
+                markdown_backend: Default::default(),
+                markdown_extensions: crate::markdown::DEFAULT_EXTENSIONS.to_vec(),
+                sanitize: Default::default(),
+                links: Default::default(),
+                search: Default::default(),
                 resume_link: Url::parse("http://google.com").unwrap(),
+                preview_token: None,
             },
             vec![],
+            crate::redirects::load("redirects.yaml").unwrap(),
             pool,
         )
         .unwrap();
@@ -313,6 +789,61 @@ mod tests {
         assert!(response.status.unwrap().is_success());
     }
 
+    #[test]
+    fn blog_pages() {
+        let server = create_server();
+        let response = request::get(
+            "http://localhost:3000/blog?page=2",
+            Headers::new(),
+            &server.handler,
+        )
+        .unwrap();
+        assert!(response.status.unwrap().is_success());
+        let body = response::extract_body_to_string(response);
+        assert!(body.contains(r#"href="?page=1""#));
+
+        let response = request::get(
+            "http://localhost:3000/blog?page=100",
+            Headers::new(),
+            &server.handler,
+        )
+        .unwrap();
+        assert_eq!(response.status.unwrap(), status::NotFound);
+    }
+
+    #[test]
+    fn date_archives() {
+        let server = create_server();
+        let response = request::get(
+            "http://localhost:3000/blog/2014/5",
+            Headers::new(),
+            &server.handler,
+        )
+        .unwrap();
+        assert!(response.status.unwrap().is_success());
+        let body = response::extract_body_to_string(response);
+        assert!(body.contains("May 2014"));
+        assert!(body.contains("Patching Vim"));
+
+        let response = request::get(
+            "http://localhost:3000/blog/2014",
+            Headers::new(),
+            &server.handler,
+        )
+        .unwrap();
+        assert!(response.status.unwrap().is_success());
+
+        for url in &[
+            "http://localhost:3000/blog/1999",
+            "http://localhost:3000/blog/foo",
+            "http://localhost:3000/blog/2014/bar",
+            "http://localhost:3000/blog/2014/13",
+        ] {
+            let response = request::get(url, Headers::new(), &server.handler).unwrap();
+            assert_eq!(response.status.unwrap(), status::NotFound, "{}", url);
+        }
+    }
+
     #[test]
     fn blog_search() {
         let server = create_server();
@@ -323,6 +854,72 @@ mod tests {
         )
         .unwrap();
         assert!(response.status.unwrap().is_success());
+
+        let response = request::get(
+            "http://localhost:3000/blog?q=%22vim&page=1",
+            Headers::new(),
+            &server.handler,
+        )
+        .unwrap();
+        assert!(response.status.unwrap().is_success());
+    }
+
+    #[test]
+    fn tags() {
+        let server = create_server();
+        let response = request::get(
+            "http://localhost:3000/blog/tags",
+            Headers::new(),
+            &server.handler,
+        )
+        .unwrap();
+        assert!(response.status.unwrap().is_success());
+
+        let response = request::get(
+            "http://localhost:3000/blog/categories/open%20source",
+            Headers::new(),
+            &server.handler,
+        )
+        .unwrap();
+        let body = response::extract_body_to_string(response);
+        assert!(body.contains("Patching Vim"));
+
+        let response = request::get(
+            "http://localhost:3000/blog/tags/nonexistent",
+            Headers::new(),
+            &server.handler,
+        )
+        .unwrap();
+        let body = response::extract_body_to_string(response);
+        assert!(body.contains("Page Not Found"));
+    }
+
+    #[test]
+    fn feeds() {
+        let server = create_server();
+        let response = request::get(
+            "http://localhost:3000/blog/feed.atom",
+            Headers::new(),
+            &server.handler,
+        )
+        .unwrap();
+        assert!(response.status.unwrap().is_success());
+        let content_type = response.headers.get::<ContentType>().unwrap().to_string();
+        assert!(content_type.starts_with("application/atom+xml"));
+        let body = response::extract_body_to_string(response);
+        assert!(body.starts_with("<?xml"));
+        assert!(body.contains("https://acrussell.com/blog/2014/5/9/patching-vim"));
+
+        let response = request::get(
+            "http://localhost:3000/blog/tags/python/feed.rss",
+            Headers::new(),
+            &server.handler,
+        )
+        .unwrap();
+        assert!(response.status.unwrap().is_success());
+        let body = response::extract_body_to_string(response);
+        assert!(body.contains("Iteration in Python"));
+        assert!(!body.contains("Patching Vim"));
     }
 
     #[test]
@@ -361,6 +958,111 @@ mod tests {
         assert!(response.status.unwrap().is_success());
     }
 
+    #[test]
+    fn blog_post() {
+        let server = create_server();
+        let response = request::get(
+            "http://localhost:3000/blog/2014/5/9/patching-vim",
+            Headers::new(),
+            &server.handler,
+        )
+        .unwrap();
+        assert!(response.status.unwrap().is_success());
+        let body = response::extract_body_to_string(response);
+        assert!(body.contains(r#"href="/blog/categories/open%20source""#));
+    }
+
+    #[test]
+    fn redirects() {
+        let server = create_server();
+        let response = request::get(
+            "http://localhost:3000/blog/2012/4/14/what-i%27ve-been-up-to-lately",
+            Headers::new(),
+            &server.handler,
+        )
+        .unwrap();
+        assert_eq!(response.status.unwrap(), status::MovedPermanently);
+        assert_eq!(
+            response.headers.get::<Location>(),
+            Some(&Location(String::from(
+                "/blog/2012/4/14/what-ive-been-up-to-lately"
+            )))
+        );
+
+        let response = request::get(
+            "http://localhost:3000/blog/2012/4/14/not-a-post",
+            Headers::new(),
+            &server.handler,
+        )
+        .unwrap();
+        assert_eq!(response.status.unwrap(), status::NotFound);
+    }
+
+    #[test]
+    fn canonical_urls() {
+        let server = create_server();
+        let response = request::get(
+            "http://localhost:3000/blog/2014/05/09/patching-vim?preview=secret",
+            Headers::new(),
+            &server.handler,
+        )
+        .unwrap();
+        assert_eq!(response.status.unwrap(), status::MovedPermanently);
+        assert_eq!(
+            response.headers.get::<Location>(),
+            Some(&Location(String::from(
+                "/blog/2014/5/9/patching-vim?preview=secret"
+            )))
+        );
+    }
+
+    #[test]
+    fn highlight_css() {
+        let server = create_server();
+        let response = request::get(
+            "http://localhost:3000/highlight.css",
+            Headers::new(),
+            &server.handler,
+        )
+        .unwrap();
+        assert!(response.status.unwrap().is_success());
+        assert!(response::extract_body_to_string(response).contains(".hl-comment"));
+    }
+
+    #[test]
+    fn is_page() {
+        let is_page = |path| {
+            super::is_page(path, |taxonomy, term| match taxonomy {
+                Taxonomy::Tag => term == "rust",
+                Taxonomy::Category => term == "open source",
+            })
+        };
+
+        assert!(is_page("/"));
+        assert!(is_page("/about/"));
+        assert!(is_page("/blog/tags/rust/feed.atom"));
+        assert!(is_page("/blog/categories/open%20source"));
+        assert!(is_page("/blog/2014"));
+        assert!(is_page("/blog/2014/5"));
+        assert!(is_page("/static/images/me.jpg"));
+        assert!(is_page("/highlight.css"));
+        assert!(!is_page("/blog/tags/rust/feed.json"));
+        assert!(!is_page("/blog/tags/vim"));
+        assert!(!is_page("/blog/categories/rust"));
+        assert!(!is_page("/blog/typo"));
+        assert!(!is_page("/blog/foo/bar"));
+        assert!(!is_page("/blog/2014/13"));
+        assert!(!is_page("/contact"));
+    }
+
+    #[test]
+    fn constant_time_eq() {
+        assert!(super::constant_time_eq(b"secret", b"secret"));
+        assert!(!super::constant_time_eq(b"secret", b"secreT"));
+        assert!(!super::constant_time_eq(b"secret", b"secrets"));
+        assert!(super::constant_time_eq(b"", b""));
+    }
+
     #[test]
     fn post_dates() {
         let server = create_server();
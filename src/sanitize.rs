@@ -0,0 +1,245 @@
+//! Sanitization of HTML rendered from markdown.
+//!
+//! Every piece of rendered markdown is cleaned according to a [`Policy`], which lists the tags,
+//! attributes and URL schemes that may appear in it. Anything else, such as `<script>` tags or
+//! `onclick` handlers, is removed.
+//!
+//! Some attributes are also checked by their values. An `<iframe>` may only embed pages from a
+//! list of hosts, such as YouTube, and the only style that may be given is the `text-align` that
+//! tables use to align their columns.
+//!
+//! The policy also decides whether raw HTML may be written in markdown at all, separately for each
+//! source of content. Where it is not allowed, raw HTML is escaped and shown as text. Shortcodes
+//! expand into raw HTML, so they can only be used where raw HTML is allowed.
+//!
+//! [`Policy`]: struct.Policy.html
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use ammonia::{self, Ammonia};
+use serde::Deserialize;
+use url::Url;
+
+use crate::html::{for_each_tag, unescape, write_tag};
+use crate::markdown::Html;
+
+/// Tags that may appear in rendered markdown, by default.
+const TAGS: &[&str] = &[
+    "a",
+    "abbr",
+    "b",
+    "blockquote",
+    "br",
+    "code",
+    "dd",
+    "del",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "iframe",
+    "img",
+    "ins",
+    "kbd",
+    "li",
+    "mark",
+    "ol",
+    "p",
+    "pre",
+    "s",
+    "span",
+    "strike",
+    "strong",
+    "sub",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "th",
+    "thead",
+    "tr",
+    "ul",
+];
+
+/// Attributes that may appear on specific tags, by default.
+const TAG_ATTRIBUTES: &[(&str, &[&str])] = &[
+    ("a", &["href", "rel", "aria-label"]),
+    (
+        "iframe",
+        &["src", "title", "frameborder", "allowfullscreen"],
+    ),
+    ("img", &["src", "alt", "width", "height"]),
+    ("ol", &["start"]),
+    ("td", &["style"]),
+    ("th", &["style"]),
+];
+
+/// Hosts that `<iframe>` tags may embed pages from, by default. These are the hosts that the
+/// `youtube` and `gist` shortcodes embed.
+const EMBED_HOSTS: &[&str] = &["www.youtube-nocookie.com", "gist.github.com"];
+
+/// Attributes that may appear on any tag, by default.
+const GENERIC_ATTRIBUTES: &[&str] = &["class", "id", "title"];
+
+/// URL schemes that links and embedded content may use, by default. Relative URLs are always
+/// allowed.
+const URL_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+/// The rules for cleaning rendered markdown.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Policy {
+    /// The tags that are allowed.
+    pub tags: BTreeSet<String>,
+
+    /// The attributes that are allowed on specific tags, by tag name.
+    pub tag_attributes: BTreeMap<String, BTreeSet<String>>,
+
+    /// The attributes that are allowed on every allowed tag.
+    pub generic_attributes: BTreeSet<String>,
+
+    /// The URL schemes that `href` and `src` attributes may use.
+    pub url_schemes: BTreeSet<String>,
+
+    /// The hosts that `<iframe>` tags may embed pages from. The `src` of any other iframe is
+    /// removed.
+    pub embed_hosts: BTreeSet<String>,
+
+    /// Whether raw HTML may be written in each source of markdown.
+    pub raw_html: RawHtml,
+}
+
+impl Policy {
+    /// Removes everything from the HTML that the policy does not allow.
+    pub fn clean(&self, html: &str) -> Html {
+        let ammonia = Ammonia {
+            tags: self.tags.iter().map(String::as_str).collect(),
+            tag_attributes: self
+                .tag_attributes
+                .iter()
+                .map(|(tag, attributes)| {
+                    (
+                        tag.as_str(),
+                        attributes.iter().map(String::as_str).collect(),
+                    )
+                })
+                .collect(),
+            generic_attributes: self.generic_attributes.iter().map(String::as_str).collect(),
+            url_schemes: self.url_schemes.iter().map(String::as_str).collect(),
+            url_relative: ammonia::UrlRelative::PassThrough,
+            link_rel: None,
+            keep_cleaned_elements: true,
+            ..Default::default()
+        };
+
+        let cleaned = ammonia.clean(html);
+        let mut output = String::with_capacity(cleaned.len());
+
+        for_each_tag(&cleaned, &mut output, |output, name, attributes| {
+            attributes.retain(|(attribute, value)| match attribute.as_str() {
+                "src" if name == "iframe" => self.is_embeddable(&unescape(value)),
+                "style" => is_alignment(&unescape(value)),
+                _ => true,
+            });
+            write_tag(output, name, attributes);
+        });
+
+        Html::new(output)
+    }
+
+    fn is_embeddable(&self, src: &str) -> bool {
+        Url::parse(src)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| self.embed_hosts.contains(host)))
+            .unwrap_or(false)
+    }
+}
+
+/// Returns whether a `style` attribute only aligns text, such as `text-align: center`.
+fn is_alignment(style: &str) -> bool {
+    let mut declaration = style.trim().trim_end_matches(';').splitn(2, ':');
+    let property = declaration.next().unwrap_or_default().trim();
+    let value = declaration.next().unwrap_or_default().trim();
+
+    property == "text-align" && ["left", "center", "right"].contains(&value)
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        let strings = |values: &[&str]| values.iter().map(|&value| value.to_owned()).collect();
+
+        Policy {
+            tags: strings(TAGS),
+            tag_attributes: TAG_ATTRIBUTES
+                .iter()
+                .map(|&(tag, attributes)| (tag.to_owned(), strings(attributes)))
+                .collect(),
+            generic_attributes: strings(GENERIC_ATTRIBUTES),
+            url_schemes: strings(URL_SCHEMES),
+            embed_hosts: strings(EMBED_HOSTS),
+            raw_html: RawHtml::default(),
+        }
+    }
+}
+
+/// Whether raw HTML may be written in each source of markdown.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct RawHtml {
+    /// Blog posts. Allowed by default.
+    pub posts: bool,
+
+    /// Project descriptions. Not allowed by default.
+    pub projects: bool,
+}
+
+impl Default for RawHtml {
+    fn default() -> Self {
+        RawHtml {
+            posts: true,
+            projects: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Policy;
+
+    #[test]
+    fn clean() {
+        let policy = Policy::default();
+
+        let html = policy.clean(
+            r##"<h2 id="setup" onclick="evil()">Setup<a class="anchor" href="#setup"></a></h2><script>evil()</script><a href="javascript:evil()">link</a><iframe src="https://www.youtube-nocookie.com/embed/abc"></iframe>"##,
+        );
+        assert_eq!(
+            &*html,
+            r##"<h2 id="setup">Setup<a class="anchor" href="#setup"></a></h2>evil()<a>link</a><iframe src="https://www.youtube-nocookie.com/embed/abc"></iframe>"##
+        );
+
+        let html = policy.clean(
+            r#"<iframe src="https://evil.example.com/" title="Evil"></iframe><iframe src="/about"></iframe><table><tr><th style="text-align: center">A</th><td style="text-align:right;">B</td><td style="position: fixed; text-align: left">C</td></tr></table>"#,
+        );
+        assert_eq!(
+            &*html,
+            r#"<iframe title="Evil"></iframe><iframe></iframe><table><tbody><tr><th style="text-align: center">A</th><td style="text-align:right;">B</td><td>C</td></tr></tbody></table>"#
+        );
+
+        let policy = Policy {
+            tags: vec![String::from("p")].into_iter().collect(),
+            ..Default::default()
+        };
+        assert_eq!(&*policy.clean("<p><em>hi</em></p>"), "<p>hi</p>");
+    }
+}
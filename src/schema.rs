@@ -8,6 +8,11 @@ table! {
         summary -> VarChar,
         url -> VarChar,
         slug -> VarChar,
+        visibility -> VarChar,
+        file_name -> VarChar,
+        word_count -> Integer,
+        reading_time -> Integer,
+        toc -> Nullable<VarChar>,
     }
 }
 
@@ -16,7 +21,34 @@ table! {
         docid -> Integer,
         title -> VarChar,
         content -> VarChar,
+        tags -> VarChar,
+        categories -> VarChar,
     }
 }
 
-allow_tables_to_appear_in_same_query!(posts, post_content);
+table! {
+    tags(post_id, name) {
+        post_id -> Integer,
+        name -> VarChar,
+    }
+}
+
+table! {
+    categories(post_id, name) {
+        post_id -> Integer,
+        name -> VarChar,
+    }
+}
+
+table! {
+    aliases(path) {
+        path -> VarChar,
+        post_id -> Integer,
+    }
+}
+
+joinable!(tags -> posts (post_id));
+joinable!(categories -> posts (post_id));
+joinable!(aliases -> posts (post_id));
+
+allow_tables_to_appear_in_same_query!(posts, post_content, tags, categories, aliases);
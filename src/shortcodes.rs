@@ -0,0 +1,320 @@
+//! Shortcodes that expand into HTML inside blog posts.
+//!
+//! A shortcode is written as `{{< name arguments >}}` anywhere in the text of a post, and is
+//! replaced by HTML before the post is rendered as markdown. Arguments are either positional or
+//! named, and must be quoted if they contain whitespace:
+//!
+//! ```text
+//! {{< youtube dQw4w9WgXcQ >}}
+//! {{< figure src="/static/images/setup.jpg" caption="My desk, circa 2014" >}}
+//! ```
+//!
+//! Shortcodes inside code blocks and code spans are left alone, so that their syntax can be shown.
+//! Elsewhere, a shortcode can be written without expanding it by commenting out its contents, as
+//! in `{{</* youtube dQw4w9WgXcQ */>}}`.
+//!
+//! Shortcodes are implemented either as Rust functions or as Handlebars templates. Every template
+//! in the `shortcodes` folder of the templates directory is registered under the name of its file.
+//! Templates receive the positional arguments as `args` and each named argument by its name.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::errors::*;
+use crate::handlebars::Handlebars;
+use crate::helpers;
+use crate::markdown;
+use crate::projects::Project;
+
+const OPEN: &str = "{{<";
+const CLOSE: &str = ">}}";
+
+/// The arguments passed to a shortcode.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Arguments {
+    positional: Vec<String>,
+    named: BTreeMap<String, String>,
+}
+
+impl Arguments {
+    /// Returns the positional argument at an index.
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.positional.get(index).map(String::as_str)
+    }
+
+    /// Returns the argument with a name.
+    pub fn named(&self, name: &str) -> Option<&str> {
+        self.named.get(name).map(String::as_str)
+    }
+
+    /// Returns the context that templates are rendered with.
+    fn context(&self) -> Value {
+        let mut context = self
+            .named
+            .iter()
+            .map(|(name, value)| (name.clone(), Value::from(value.as_str())))
+            .collect::<serde_json::Map<_, _>>();
+        context.insert(String::from("args"), json!(self.positional));
+        Value::Object(context)
+    }
+}
+
+type ShortcodeFn = dyn Fn(&Arguments) -> Result<String> + Send + Sync;
+
+/// The shortcodes that may be used in blog posts.
+pub struct Shortcodes {
+    functions: HashMap<String, Box<ShortcodeFn>>,
+    templates: Handlebars,
+}
+
+impl Default for Shortcodes {
+    fn default() -> Self {
+        Shortcodes {
+            functions: HashMap::new(),
+            templates: Handlebars::new(),
+        }
+    }
+}
+
+impl Shortcodes {
+    /// Returns the shortcodes for the website.
+    ///
+    /// The templates in the `shortcodes` folder of the templates directory are registered, along
+    /// with a `project` shortcode that embeds a project from the projects page, rendered with the
+    /// `project` template.
+    pub fn load<P>(templates_directory: P, projects: &[Project]) -> Result<Shortcodes>
+    where
+        P: AsRef<Path>,
+    {
+        let templates_directory = templates_directory.as_ref();
+        let mut shortcodes = Shortcodes::default();
+
+        let entries = fs::read_dir(templates_directory.join("shortcodes"))
+            .chain_err(|| "could not read shortcodes directory")?;
+        for entry in entries {
+            let path = entry?.path();
+            if path
+                .extension()
+                .map_or(false, |extension| extension == "hbs")
+            {
+                let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+                shortcodes.register_template(&name, &fs::read_to_string(&path)?)?;
+            }
+        }
+
+        let mut card_templates = Handlebars::new();
+        card_templates.register_helper("join", Box::new(helpers::join));
+        card_templates
+            .register_template_file("project", templates_directory.join("project.hbs"))?;
+        let cards = projects
+            .iter()
+            .map(|project| {
+                Ok((
+                    project.name().to_owned(),
+                    card_templates.render("project", project)?,
+                ))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
+
+        shortcodes.register_fn("project", move |args| {
+            let name = match args.get(0).or_else(|| args.named("name")) {
+                Some(name) => name,
+                None => bail!("the project shortcode requires the name of a project"),
+            };
+            match cards.get(name) {
+                Some(card) => Ok(card.to_owned()),
+                None => bail!("unknown project `{}`", name),
+            }
+        });
+
+        Ok(shortcodes)
+    }
+
+    /// Registers a shortcode implemented by a function, which returns the HTML to replace the
+    /// shortcode with.
+    pub fn register_fn<F>(&mut self, name: &str, shortcode: F)
+    where
+        F: Fn(&Arguments) -> Result<String> + Send + Sync + 'static,
+    {
+        self.functions.insert(name.to_owned(), Box::new(shortcode));
+    }
+
+    /// Registers a shortcode implemented by a Handlebars template.
+    pub fn register_template(&mut self, name: &str, template: &str) -> Result<()> {
+        self.templates.register_template_string(name, template)?;
+        Ok(())
+    }
+
+    /// Replaces every shortcode outside of code in a string of markdown with its HTML.
+    pub fn expand(&self, markdown: &str) -> Result<String> {
+        markdown::rewrite_text(markdown, |text, output| self.expand_in_text(text, output))
+    }
+
+    fn expand_in_text(&self, text: &str, output: &mut String) -> Result<()> {
+        let mut rest = text;
+
+        while let Some(start) = rest.find(OPEN) {
+            output.push_str(&rest[..start]);
+
+            let inner_start = start + OPEN.len();
+            let end = match rest[inner_start..].find(CLOSE) {
+                Some(end) => inner_start + end,
+                None => bail!(
+                    "unclosed shortcode `{}`",
+                    rest[start..].lines().next().unwrap()
+                ),
+            };
+            let inner = rest[inner_start..end].trim();
+
+            if inner.starts_with("/*") && inner.ends_with("*/") && inner.len() >= 4 {
+                output.push_str(OPEN);
+                output.push(' ');
+                output.push_str(inner[2..inner.len() - 2].trim());
+                output.push(' ');
+                output.push_str(CLOSE);
+            } else {
+                let html = self
+                    .expand_shortcode(inner)
+                    .chain_err(|| format!("could not expand shortcode `{}`", inner))?;
+                output.push_str(html.trim());
+            }
+
+            rest = &rest[end + CLOSE.len()..];
+        }
+
+        output.push_str(rest);
+        Ok(())
+    }
+
+    fn expand_shortcode(&self, shortcode: &str) -> Result<String> {
+        let mut tokens = tokenize(shortcode)?.into_iter();
+        let name = match tokens.next() {
+            Some(name) => name,
+            None => bail!("missing shortcode name"),
+        };
+
+        let mut arguments = Arguments::default();
+        for token in tokens {
+            match token.find('=') {
+                Some(equals) => {
+                    let value = unquote(&token[equals + 1..]);
+                    arguments
+                        .named
+                        .insert(token[..equals].to_owned(), value.to_owned());
+                }
+                None => arguments.positional.push(unquote(&token).to_owned()),
+            }
+        }
+
+        if let Some(function) = self.functions.get(&name) {
+            function(&arguments)
+        } else if self.templates.get_template(&name).is_some() {
+            Ok(self.templates.render(&name, &arguments.context())?)
+        } else {
+            bail!("unknown shortcode `{}`", name)
+        }
+    }
+}
+
+/// Splits the contents of a shortcode on whitespace, except for whitespace inside quotes.
+fn tokenize(shortcode: &str) -> Result<Vec<String>> {
+    let mut tokens = vec![];
+    let mut token = String::new();
+    let mut in_quotes = false;
+
+    for c in shortcode.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                token.push(c);
+            }
+            _ if c.is_whitespace() && !in_quotes => {
+                if !token.is_empty() {
+                    tokens.push(token);
+                    token = String::new();
+                }
+            }
+            _ => token.push(c),
+        }
+    }
+
+    if in_quotes {
+        bail!("unterminated quote");
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+fn unquote(value: &str) -> &str {
+    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        &value[1..value.len() - 1]
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Shortcodes;
+
+    #[test]
+    fn expand() {
+        let mut shortcodes = Shortcodes::load("templates", &[]).unwrap();
+        shortcodes.register_fn("shout", |args| Ok(args.get(0).unwrap().to_uppercase()));
+
+        let expanded = shortcodes
+            .expand("Hey {{< shout \"you there\" >}}!\n\n{{</* shout hi */>}}\n")
+            .unwrap();
+        assert_eq!(expanded, "Hey YOU THERE!\n\n{{< shout hi >}}\n");
+
+        let figure = shortcodes
+            .expand(r#"{{< figure src="/static/me.jpg" caption="<Me>" >}}"#)
+            .unwrap();
+        assert!(figure.contains(r#"<img src="/static/me.jpg" alt="&lt;Me&gt;">"#));
+        assert!(figure.contains("<figcaption>&lt;Me&gt;</figcaption>"));
+
+        let video = shortcodes
+            .expand("{{< youtube\n    dQw4w9WgXcQ >}}")
+            .unwrap();
+        assert!(video.contains(r#"src="https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ""#));
+
+        let gist = shortcodes.expand("{{< gist euclio/abc123 >}}").unwrap();
+        assert!(gist.contains(r#"src="https://gist.github.com/euclio/abc123.pibb""#));
+    }
+
+    #[test]
+    fn code() {
+        let shortcodes = Shortcodes::load("templates", &[]).unwrap();
+
+        let markdown = "Embed with `{{< youtube id >}}`:\n\n\
+                        ```\n{{< youtube dQw4w9WgXcQ >}}\n```\n\n\
+                        {{< youtube dQw4w9WgXcQ >}}\n";
+        let expanded = shortcodes.expand(markdown).unwrap();
+        assert!(expanded.starts_with(
+            "Embed with `{{< youtube id >}}`:\n\n```\n{{< youtube dQw4w9WgXcQ >}}\n```\n\n<"
+        ));
+        assert_eq!(expanded.matches("youtube-nocookie").count(), 1);
+
+        let markdown = "Paragraph\n    {{< youtube dQw4w9WgXcQ >}}\n\n    {{< youtube id >}}\n";
+        let expanded = shortcodes.expand(markdown).unwrap();
+        assert!(expanded.ends_with("\n\n    {{< youtube id >}}\n"));
+        assert_eq!(expanded.matches("youtube-nocookie").count(), 1);
+    }
+
+    #[test]
+    fn invalid_shortcodes() {
+        let shortcodes = Shortcodes::load("templates", &[]).unwrap();
+
+        assert!(shortcodes.expand("{{< nonexistent >}}").is_err());
+        assert!(shortcodes.expand("{{< youtube abc").is_err());
+        assert!(shortcodes.expand("{{< figure src=\"abc >}}").is_err());
+        assert!(shortcodes.expand("{{< project >}}").is_err());
+        assert!(shortcodes.expand("{{< project website >}}").is_err());
+    }
+}
@@ -0,0 +1,59 @@
+//! Conversion of titles into strings that are safe to use in URLs.
+
+use deunicode;
+
+/// Converts a title into a string that is safe to use as a URL path segment.
+///
+/// Non-ASCII characters are transliterated, apostrophes are dropped, and any other runs of
+/// punctuation or whitespace become a single dash.
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::new();
+
+    for c in deunicode::deunicode(title).chars() {
+        match c {
+            'a'..='z' | '0'..='9' => slug.push(c),
+            'A'..='Z' => slug.push(c.to_ascii_lowercase()),
+            '\'' => (),
+            _ if !slug.is_empty() && !slug.ends_with('-') => slug.push('-'),
+            _ => (),
+        }
+    }
+
+    if slug.ends_with('-') {
+        slug.pop();
+    }
+
+    slug
+}
+
+/// Returns whether a slug that was written by hand may be used in a URL unchanged.
+pub fn is_valid_slug(slug: &str) -> bool {
+    !slug.is_empty()
+        && !slug.starts_with('-')
+        && !slug.ends_with('-')
+        && slug
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn slugs() {
+        assert_eq!(
+            super::slugify("What I've Been Up To Lately"),
+            "what-ive-been-up-to-lately"
+        );
+        assert_eq!(
+            super::slugify("Signing JARs: Doodler and the File System"),
+            "signing-jars-doodler-and-the-file-system"
+        );
+        assert_eq!(super::slugify("Massive Updates!"), "massive-updates");
+        assert_eq!(super::slugify("Café Crème"), "cafe-creme");
+
+        assert!(super::is_valid_slug("patching-vim-2"));
+        assert!(!super::is_valid_slug("Patching Vim"));
+        assert!(!super::is_valid_slug("-patching-vim"));
+        assert!(!super::is_valid_slug(""));
+    }
+}
@@ -0,0 +1,95 @@
+//! Reloading blog posts when they change on disk.
+//!
+//! Posts are parsed once at startup by `blog::load`. Afterwards, the blog directory is watched so
+//! that individual posts may be added, edited or removed without restarting the server.
+
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+use log::*;
+use notify::{DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::blog;
+use crate::config::Config;
+use crate::errors::*;
+use crate::persistence::ConnectionPool;
+use crate::shortcodes::Shortcodes;
+
+/// How long to wait for a file to stop changing before reloading it.
+const DEBOUNCE_DELAY: Duration = Duration::from_secs(1);
+
+/// Starts watching a directory of blog posts in the background, updating the database whenever a
+/// post is created, modified or deleted.
+pub fn watch_posts<P>(
+    directory: P,
+    pool: ConnectionPool,
+    config: Config,
+    shortcodes: Arc<Shortcodes>,
+) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    let (tx, rx) = mpsc::channel();
+
+    let mut watcher: RecommendedWatcher = Watcher::new(tx, DEBOUNCE_DELAY)?;
+    watcher.watch(&directory, RecursiveMode::NonRecursive)?;
+    info!("watching {:?} for changes", directory.as_ref());
+
+    thread::spawn(move || {
+        // The watcher stops watching when it is dropped, so it must live as long as the thread.
+        let _watcher = watcher;
+
+        for event in rx {
+            for path in changed_posts(event) {
+                if let Err(e) = reload(&pool, &config, &shortcodes, &path) {
+                    error!("could not reload {:?}: {}", path, e);
+                    for e in e.iter().skip(1) {
+                        error!("caused by: {}", e);
+                    }
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Returns the posts whose files were affected by a file system event.
+fn changed_posts(event: DebouncedEvent) -> Vec<PathBuf> {
+    let paths = match event {
+        DebouncedEvent::Create(path)
+        | DebouncedEvent::Write(path)
+        | DebouncedEvent::Remove(path) => vec![path],
+        DebouncedEvent::Rename(from, to) => vec![from, to],
+        DebouncedEvent::Rescan => {
+            warn!("blog directory must be rescanned, restart the server to pick up all changes");
+            vec![]
+        }
+        DebouncedEvent::Error(e, path) => {
+            error!("error watching {:?}: {}", path, e);
+            vec![]
+        }
+        DebouncedEvent::NoticeWrite(_)
+        | DebouncedEvent::NoticeRemove(_)
+        | DebouncedEvent::Chmod(_) => {
+            vec![]
+        }
+    };
+
+    paths
+        .into_iter()
+        .filter(|path| blog::is_post_file(path))
+        .collect()
+}
+
+fn reload(
+    pool: &ConnectionPool,
+    config: &Config,
+    shortcodes: &Shortcodes,
+    path: &Path,
+) -> Result<()> {
+    let connection = pool.get().chain_err(|| "database connection timed out")?;
+    blog::reload_post(path, &connection, config, shortcodes)
+}
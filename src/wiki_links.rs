@@ -0,0 +1,148 @@
+//! Wiki-style links between blog posts.
+//!
+//! A wiki link is written as `[[Target]]` or `[[Target|link text]]`, where the target is the title
+//! or slug of another post. Links are replaced with regular markdown links before the post is
+//! rendered, so that they follow the other post if its URL changes.
+//!
+//! Links inside code are left alone. So is anything that starts or ends with whitespace inside the
+//! brackets, such as the shell conditional `[[ -f file ]]`.
+
+use crate::markdown;
+
+/// A post that a wiki link may point at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    /// The URL of the post.
+    pub url: String,
+
+    /// The title of the post, used as the link text if none is given.
+    pub title: String,
+}
+
+/// Replaces every wiki link in a string of markdown with a markdown link.
+///
+/// `resolve` is called with the target of each link. If it does not return a post, the text of
+/// the link is returned as the error.
+pub fn replace<F>(markdown: &str, mut resolve: F) -> Result<String, String>
+where
+    F: FnMut(&str) -> Option<Target>,
+{
+    markdown::rewrite_text(markdown, |text, output| {
+        replace_in_text(text, &mut resolve, output)
+    })
+}
+
+/// Returns the targets of the wiki links in a string of markdown, in the order they appear.
+pub fn targets(markdown: &str) -> Vec<String> {
+    let mut targets = vec![];
+
+    let _ = replace(markdown, |target| {
+        targets.push(target.to_owned());
+        Some(Target {
+            url: String::new(),
+            title: String::new(),
+        })
+    });
+
+    targets
+}
+
+fn replace_in_text<F>(text: &str, resolve: &mut F, output: &mut String) -> Result<(), String>
+where
+    F: FnMut(&str) -> Option<Target>,
+{
+    let mut rest = text;
+
+    while let Some(start) = rest.find('[') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let link = rest
+            .strip_prefix("[[")
+            .and_then(|link| link.find("]]").map(|end| &link[..end]))
+            .filter(|link| is_wiki_link(link));
+
+        match link {
+            Some(link) => {
+                let (target, text) = match link.find('|') {
+                    Some(bar) => (link[..bar].trim(), Some(link[bar + 1..].trim())),
+                    None => (link, None),
+                };
+                let post = resolve(target).ok_or_else(|| format!("[[{}]]", link))?;
+                let text = text.unwrap_or(&post.title);
+
+                output.push('[');
+                output.push_str(&text.replace('[', "\\[").replace(']', "\\]"));
+                output.push_str("](");
+                output.push_str(&post.url);
+                output.push(')');
+
+                rest = &rest[link.len() + 4..];
+            }
+            None => {
+                output.push('[');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    output.push_str(rest);
+    Ok(())
+}
+
+fn is_wiki_link(link: &str) -> bool {
+    !link.is_empty()
+        && link.trim() == link
+        && !link.starts_with('|')
+        && !link.contains(|c| c == '[' || c == '\n')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Target;
+
+    fn resolve(target: &str) -> Option<Target> {
+        match target {
+            "Patching Vim" | "patching-vim" => Some(Target {
+                url: String::from("/blog/2014/5/9/patching-vim"),
+                title: String::from("Patching Vim"),
+            }),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn replace() {
+        let markdown = "See [[Patching Vim]] and [[patching-vim|my patch]].\n\n\
+                        `[[nope]]` [[ -f file ]] [link](/about)\n\n\
+                        ```\n[[nope]]\n```\n";
+        assert_eq!(
+            super::replace(markdown, resolve).unwrap(),
+            "See [Patching Vim](/blog/2014/5/9/patching-vim) and \
+             [my patch](/blog/2014/5/9/patching-vim).\n\n\
+             `[[nope]]` [[ -f file ]] [link](/about)\n\n\
+             ```\n[[nope]]\n```\n"
+        );
+
+        assert_eq!(
+            super::replace("[[patching-vim]]", resolve).unwrap(),
+            "[Patching Vim](/blog/2014/5/9/patching-vim)"
+        );
+    }
+
+    #[test]
+    fn targets() {
+        assert_eq!(
+            super::targets("[[Patching Vim]], `[[nope]]` and [[patching-vim|this]]"),
+            &["Patching Vim", "patching-vim"]
+        );
+    }
+
+    #[test]
+    fn unresolved() {
+        assert_eq!(
+            super::replace("Read [[Patching Emacs|this]].", resolve),
+            Err(String::from("[[Patching Emacs|this]]"))
+        );
+    }
+}